@@ -1,165 +1,2184 @@
+use crate::compact::{CompactNB, TokenScoring};
+use crate::counts::{grow_feature_rows, grow_slice, CountStorage, FeatureCounts};
+use crate::remap::FeatureRemap;
+use crate::tokenizer::Tokenizer;
+use fixedbitset::FixedBitSet;
 use serde::{de::Error, Deserialize, Serialize};
-use std::{collections::HashMap, io::Read, vec};
+use std::{borrow::Cow, collections::HashMap, io::Read, vec};
 
 /// Base trait for all Naïve Bayes classifiers
 pub trait NaiveBayesClassifier {
     fn fit(&mut self, tokens: &[usize], label: usize);
     fn predict(&self, tokens: &[usize]) -> usize;
-    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]>;
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]>;
+
+    /// Returns the target label probabilities for the tokenized text, normalized to sum to 1.
+    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
+        normalize_log_likelihoods(&self.joint_log_likelihood(tokens))
+    }
+
+    /// Returns the log-likelihood of the tokenized text under its most likely target label.
+    fn max_log_likelihood(&self, tokens: &[usize]) -> f64 {
+        self.joint_log_likelihood(tokens)
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// Returns the log-likelihood of the tokenized text marginalized over all target labels.
+    fn marginal_log_likelihood(&self, tokens: &[usize]) -> f64 {
+        log_sum_exp(&self.joint_log_likelihood(tokens))
+    }
+
+    /// Returns whether the tokenized text looks unlike the training data, that is if its marginal
+    /// log-likelihood per token is below the threshold.
+    /// Texts without any known token are always considered outliers.
+    fn is_outlier(&self, tokens: &[usize], threshold: f64) -> bool {
+        tokens.is_empty()
+            || self.marginal_log_likelihood(tokens) / (tokens.len() as f64) < threshold
+    }
+
+    /// Returns the human-readable names of the target labels, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        None
+    }
+
+    /// Predicts the name of the target label for the tokenized text.
+    /// Panics if the classifier has no class names.
+    fn predict_label(&self, tokens: &[usize]) -> &str {
+        let class_names = self.class_names().expect("Classifier has no class names");
+        &class_names[self.predict(tokens)]
+    }
+
+    /// Returns the target label probabilities for the tokenized text, keyed by class name.
+    /// Panics if the classifier has no class names.
+    fn predict_probas_named(&self, tokens: &[usize]) -> Vec<(&str, f64)> {
+        let class_names = self.class_names().expect("Classifier has no class names");
+        class_names
+            .iter()
+            .map(|name| &**name)
+            .zip(self.predict_probas(tokens).iter().copied())
+            .collect()
+    }
+}
+
+/// A classifier whose fitted samples can be removed without refitting it from scratch.
+pub trait DecrementalClassifier: NaiveBayesClassifier {
+    /// Removes the contribution of a previously fitted tokenized text from the classifier.
+    fn unfit(&mut self, tokens: &[usize], label: usize);
+}
+
+/// Fits the classifier on a stream of (text, label) samples tokenized with the tokenizer, without
+/// materializing the corpus. Returns the number of fitted samples.
+pub fn fit_stream<T: AsRef<str>>(
+    classifier: &mut dyn NaiveBayesClassifier,
+    tokenizer: &Tokenizer,
+    samples: impl IntoIterator<Item = (T, usize)>,
+) -> usize {
+    let mut tokens = Vec::new();
+    let mut n_samples = 0;
+    for (text, label) in samples {
+        tokenizer.tokenize_into(text.as_ref(), &mut tokens);
+        classifier.fit(&tokens, label);
+        n_samples += 1;
+    }
+    n_samples
+}
+
+/// Converts joint log-likelihoods into probabilities summing to 1.
+fn normalize_log_likelihoods(log_likelihoods: &[f64]) -> Box<[f64]> {
+    let log_evidence = log_sum_exp(log_likelihoods);
+
+    log_likelihoods
+        .iter()
+        .map(|log_prob| (log_prob - log_evidence).exp())
+        .collect::<Vec<f64>>()
+        .into_boxed_slice()
+}
+
+/// Computes `ln(sum(exp(x)))` without underflowing on very negative values.
+pub(crate) fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Picks the predicted label from the label probabilities. Two-label models with a decision
+/// threshold predict label 1 whenever its probability reaches the threshold instead of the argmax.
+///
+/// Labels with a NaN probability never win unless all probabilities are NaN, in which case all
+/// labels are tied. Ties are broken according to the tie-break policy, using `prior_score`, which
+/// increases with the prior of a label, to compare the tied labels.
+pub(crate) fn decide(
+    probas: &[f64],
+    decision_threshold: Option<f64>,
+    tie_break: TieBreak,
+    prior_score: impl Fn(usize) -> f64,
+) -> usize {
+    if let (Some(threshold), 2) = (decision_threshold, probas.len()) {
+        return (probas[1] >= threshold) as usize;
+    }
+
+    let all_nan = probas.iter().all(|p| p.is_nan());
+    let max = probas
+        .iter()
+        .copied()
+        .filter(|p| !p.is_nan())
+        .fold(f64::NEG_INFINITY, f64::max);
+    let tied: Vec<usize> = (0..probas.len())
+        .filter(|&label| all_nan || probas[label] == max)
+        .collect();
+
+    match tie_break {
+        _ if tied.len() == 1 => tied[0],
+        TieBreak::LowestIndex => tied[0],
+        TieBreak::HighestPrior => tied
+            .into_iter()
+            .reduce(|best, label| {
+                if prior_score(label) > prior_score(best) {
+                    label
+                } else {
+                    best
+                }
+            })
+            .unwrap(),
+        TieBreak::Error => panic!("Labels {:?} are tied", tied),
+    }
+}
+
+/// Caps the raw frequency of a token in a document and applies sublinear scaling, if enabled.
+pub(crate) fn scale_frequency(
+    mut frequency: f64,
+    max_tf: Option<usize>,
+    sublinear_tf: bool,
+) -> f64 {
+    if let Some(max_tf) = max_tf {
+        frequency = frequency.min(max_tf as f64);
+    }
+    if sublinear_tf {
+        frequency = 1.0 + frequency.ln();
+    }
+    frequency
+}
+
+/// Checks that a decision threshold is a valid probability.
+fn validate_decision_threshold(decision_threshold: Option<f64>) {
+    if let Some(threshold) = decision_threshold {
+        assert!(
+            (0.0..=1.0).contains(&threshold),
+            "Decision threshold must be in the [0, 1] range"
+        );
+    }
+}
+
+/// How the prior probabilities of the target labels are estimated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum ClassPrior {
+    /// Priors are learned from the smoothed label frequencies of the fitted samples.
+    #[default]
+    Fitted,
+    /// All labels are considered equally likely.
+    Uniform,
+    /// User supplied prior probabilities for each label, one per target label.
+    Custom(Box<[f64]>),
+}
+
+impl ClassPrior {
+    /// Returns the log prior probability of the label.
+    fn log_prior(
+        &self,
+        label: usize,
+        label_count: f64,
+        total_samples: f64,
+        n_labels: usize,
+        laplace_factor: f64,
+    ) -> f64 {
+        match self {
+            ClassPrior::Fitted => ((label_count + laplace_factor)
+                / (total_samples + n_labels as f64 * laplace_factor))
+                .ln(),
+            ClassPrior::Uniform => -(n_labels as f64).ln(),
+            ClassPrior::Custom(priors) => priors[label].ln(),
+        }
+    }
+
+    /// Panics if the custom priors aren't valid probabilities of exactly `n_labels` labels.
+    fn validate(&self, n_labels: usize) {
+        if let ClassPrior::Custom(priors) = self {
+            assert_eq!(
+                priors.len(),
+                n_labels,
+                "There must be exactly one custom prior per target label"
+            );
+            assert!(
+                priors.iter().all(|&p| (0.0..=1.0).contains(&p)),
+                "Custom priors must be in the [0, 1] range"
+            );
+            assert!(
+                (priors.iter().sum::<f64>() - 1.0).abs() < 1e-6,
+                "Custom priors must sum to 1"
+            );
+        }
+    }
+
+    /// Panics if the classifier can't grow to `n_labels` labels, which custom priors don't cover.
+    fn validate_growth(&self, n_labels: usize) {
+        if let ClassPrior::Custom(priors) = self {
+            assert!(
+                n_labels <= priors.len(),
+                "Can't grow past the labels covered by the custom priors"
+            );
+        }
+    }
+}
+
+/// How the feature probabilities given each label are smoothed to account for unseen tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Smoothing {
+    /// Additive smoothing using the classifier Laplace smoothing factor.
+    #[default]
+    Laplace,
+    /// Interpolation between the per-label and the corpus-wide token distributions, with the
+    /// specified weight in `]0, 1]` given to the corpus-wide distribution.
+    JelinekMercer(f64),
+    /// Bayesian smoothing using a Dirichlet prior over the corpus-wide token distribution, with the
+    /// specified pseudo-count `mu`.
+    Dirichlet(f64),
+    /// Subtracts the specified discount from every seen token count and redistributes the freed
+    /// probability mass according to the corpus-wide token distribution.
+    AbsoluteDiscounting(f64),
+}
+
+impl Smoothing {
+    /// Returns the smoothed probability of a token given a label.
+    ///
+    /// `count` is the token count for the label, `total` the normalizing total of the label and
+    /// `distinct` the number of distinct tokens seen for the label. Laplace smoothing adds the
+    /// token smoothing factor to its count and the total smoothing mass of all outcomes to the total.
+    fn probability(
+        &self,
+        count: f64,
+        total: f64,
+        distinct: usize,
+        laplace_factor: f64,
+        laplace_mass: f64,
+        corpus_prob: impl FnOnce() -> f64,
+    ) -> f64 {
+        match *self {
+            Smoothing::Laplace => (count + laplace_factor) / (total + laplace_mass),
+            Smoothing::JelinekMercer(lambda) => {
+                let label_prob = if total > 0.0 { count / total } else { 0.0 };
+                (1.0 - lambda) * label_prob + lambda * corpus_prob()
+            }
+            Smoothing::Dirichlet(mu) => (count + mu * corpus_prob()) / (total + mu),
+            Smoothing::AbsoluteDiscounting(_) if total <= 0.0 => corpus_prob(),
+            Smoothing::AbsoluteDiscounting(delta) => {
+                let backoff_weight = (delta * distinct as f64 / total).min(1.0);
+                (count - delta).max(0.0) / total + backoff_weight * corpus_prob()
+            }
+        }
+    }
+
+    /// Panics if the smoothing parameters are out of their valid range.
+    fn validate(&self) {
+        match *self {
+            Smoothing::Laplace => {}
+            Smoothing::JelinekMercer(lambda) => assert!(
+                lambda > 0.0 && lambda <= 1.0,
+                "Jelinek-Mercer interpolation weight must be in the ]0, 1] range"
+            ),
+            Smoothing::Dirichlet(mu) => assert!(mu > 0.0, "Dirichlet prior mu must be positive"),
+            Smoothing::AbsoluteDiscounting(delta) => assert!(
+                (0.0..=1.0).contains(&delta),
+                "Absolute discount must be in the [0, 1] range"
+            ),
+        }
+    }
+}
+
+/// How the features to keep are selected when pruning a trained classifier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pruning {
+    /// Keeps the features whose total count over all labels is at least the specified count.
+    MinCount(f64),
+    /// Keeps the specified number of features with the highest total count over all labels.
+    TopK(usize),
+}
+
+impl Pruning {
+    /// Returns the remapping keeping the selected features given their total counts.
+    fn select(&self, feature_totals: &[f64]) -> FeatureRemap {
+        match *self {
+            Pruning::MinCount(min_count) => {
+                FeatureRemap::new(feature_totals.len(), |idx| feature_totals[idx] >= min_count)
+            }
+            Pruning::TopK(k) => FeatureRemap::top_k(feature_totals, k),
+        }
+    }
+}
+
+/// Per-feature Laplace smoothing factors.
+#[derive(Clone, Serialize, Deserialize)]
+struct FeatureLaplaceFactors {
+    factors: Box<[f64]>,
+    /// Sum of all the factors.
+    total: f64,
+}
+
+impl FeatureLaplaceFactors {
+    fn new(factors: Box<[f64]>) -> Self {
+        assert!(
+            factors.iter().all(|&alpha| alpha >= 0.0),
+            "Laplace smoothing factors must be non-negative"
+        );
+        let total = factors.iter().sum();
+        Self { factors, total }
+    }
+
+    /// Returns the factors of the kept features, reindexed according to the remapping.
+    fn remap(&self, remap: &FeatureRemap) -> Self {
+        Self::new(remap.kept().iter().map(|&idx| self.factors[idx]).collect())
+    }
+
+    /// Grows the factors to `n_features` features, using the default factor for the new ones.
+    fn grow(&mut self, n_features: usize, default_factor: f64) {
+        if self.factors.len() < n_features {
+            self.total += (n_features - self.factors.len()) as f64 * default_factor;
+            let mut grown = std::mem::take(&mut self.factors).into_vec();
+            grown.resize(n_features, default_factor);
+            self.factors = grown.into_boxed_slice();
+        }
+    }
+}
+
+/// How much each target label contributes to the fitted likelihood and prior estimates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum ClassWeight {
+    /// All labels contribute equally.
+    #[default]
+    None,
+    /// Labels are weighted inversely proportionally to their frequency, as `n_samples / (n_labels * n_label_samples)`.
+    Balanced,
+    /// User supplied weights for each label.
+    Custom(Box<[f64]>),
+}
+
+impl ClassWeight {
+    /// Returns the weight of every label given the fitted label counts, along with the weighted
+    /// total number of samples.
+    fn weights(&self, target_counts: &[f64]) -> (Box<[f64]>, f64) {
+        let total_samples: f64 = target_counts.iter().sum();
+
+        let weights = target_counts
+            .iter()
+            .enumerate()
+            .map(|(label, &count)| match self {
+                ClassWeight::None => 1.0,
+                ClassWeight::Balanced if count == 0.0 => 1.0,
+                ClassWeight::Balanced => total_samples / (target_counts.len() as f64 * count),
+                ClassWeight::Custom(weights) => weights.get(label).copied().unwrap_or(1.0),
+            })
+            .collect::<Vec<f64>>()
+            .into_boxed_slice();
+
+        let weighted_samples = target_counts
+            .iter()
+            .zip(weights.iter())
+            .map(|(count, weight)| count * weight)
+            .sum();
+
+        (weights, weighted_samples)
+    }
+}
+
+/// How `predict` picks the label when several labels have the same highest probability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TieBreak {
+    /// Picks the tied label with the lowest index.
+    #[default]
+    LowestIndex,
+    /// Picks the tied label with the highest prior probability, then the lowest index.
+    HighestPrior,
+    /// Panics when labels are tied.
+    Error,
+}
+
+/// Log-probabilities precomputed when finalizing a classifier, for fast inference.
+#[derive(Clone)]
+pub(crate) struct LogProbTables {
+    /// Log prior probability of each label.
+    pub class_log_prior: Box<[f64]>,
+    /// Log probability of each feature given each label, indexed as `label * n_features + token`.
+    pub feature_log_prob: Box<[f64]>,
+    /// Number of features.
+    pub n_features: usize,
+}
+
+impl LogProbTables {
+    /// Returns the joint log-likelihood of each label for the (token, count) pairs.
+    fn joint_log_likelihood(
+        &self,
+        token_counts: impl Iterator<Item = (usize, f64)> + Clone,
+    ) -> Box<[f64]> {
+        self.class_log_prior
+            .iter()
+            .enumerate()
+            .map(|(tgt, log_prior)| {
+                let log_probs = &self.feature_log_prob[tgt * self.n_features..][..self.n_features];
+                log_prior
+                    + token_counts
+                        .clone()
+                        .filter_map(|(token, count)| log_probs.get(token).map(|lp| count * lp))
+                        .sum::<f64>()
+            })
+            .collect::<Vec<f64>>()
+            .into_boxed_slice()
+    }
+
+    /// Returns the log probability of the token given the label.
+    #[inline]
+    fn feature(&self, label: usize, token: usize) -> f64 {
+        self.feature_log_prob[label * self.n_features + token]
+    }
+
+    /// Returns the `n` tokens with the highest log-likelihood ratio between the label and its most
+    /// likely competing label, sorted by decreasing ratio.
+    fn top_features(&self, label: usize, n: usize) -> Vec<(usize, f64)> {
+        let n_labels = self.class_log_prior.len();
+        assert!(label < n_labels, "Label is out of range");
+
+        let mut ratios: Vec<(usize, f64)> = (0..self.n_features)
+            .map(|token| {
+                let competitor = (0..n_labels)
+                    .filter(|&other| other != label)
+                    .map(|other| self.feature(other, token))
+                    .fold(f64::NEG_INFINITY, f64::max);
+                (token, self.feature(label, token) - competitor)
+            })
+            .collect();
+
+        ratios.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ratios.truncate(n);
+        ratios
+    }
+
+    /// Writes the tables as CSV with one column per label, a first row of log priors then one row of
+    /// log probabilities per token.
+    fn write_csv(
+        &self,
+        file: &mut dyn std::io::Write,
+        class_names: Option<&[Box<str>]>,
+        tokenizer: Option<&Tokenizer>,
+    ) -> std::io::Result<()> {
+        let n_labels = self.class_log_prior.len();
+
+        write!(file, "token")?;
+        for label in 0..n_labels {
+            match class_names {
+                Some(names) => write!(file, ",{}", csv_field(&names[label]))?,
+                None => write!(file, ",{}", label)?,
+            }
+        }
+        writeln!(file)?;
+
+        write!(file, "__prior__")?;
+        for log_prior in self.class_log_prior.iter() {
+            write!(file, ",{}", log_prior)?;
+        }
+        writeln!(file)?;
+
+        for token in 0..self.n_features {
+            match tokenizer.and_then(|tokenizer| tokenizer.token(token)) {
+                Some(word) => write!(file, "{}", csv_field(word))?,
+                None => write!(file, "{}", token)?,
+            }
+            for label in 0..n_labels {
+                write!(file, ",{}", self.feature(label, token))?;
+            }
+            writeln!(file)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// A Naive Bayes classifier using binary features (presence or absence of a specific word).
+#[derive(Serialize, Deserialize)]
+pub struct BernouliNB {
+    /// Weighted feature counts for each label.
+    feature_counts: FeatureCounts,
+    /// Number of features.
+    n_features: usize,
+    /// Total number of samples
+    total_samples: f64,
+    /// Count of target labels
+    target_counts: Box<[f64]>,
+    /// The Laplace smoothing factor
+    laplace_factor: f64,
+    /// How the label priors are estimated.
+    class_prior: ClassPrior,
+    /// How much each label contributes to the estimates.
+    class_weight: ClassWeight,
+    /// Precomputed log-probabilities, if the classifier has been finalized.
+    #[serde(skip)]
+    log_prob_tables: Option<LogProbTables>,
+    /// How the feature probabilities are smoothed.
+    smoothing: Smoothing,
+    /// Per-feature Laplace smoothing factors overriding the scalar one, if any.
+    feature_laplace_factors: Option<FeatureLaplaceFactors>,
+    /// Minimal number of occurrences for a token to be considered present in a document.
+    binarize_threshold: usize,
+    /// Probability of label 1 above which two-label models predict it, instead of the argmax.
+    decision_threshold: Option<f64>,
+    /// Human-readable names of the target labels, if any.
+    class_names: Option<Box<[Box<str>]>>,
+    /// How ties between labels are broken when predicting.
+    tie_break: TieBreak,
+    /// Index of the token unknown words are mapped to, scored as a token never seen, if any.
+    unk_token: Option<usize>,
+}
+
+impl BernouliNB {
+    pub fn new(n_features: usize, n_labels: usize, laplace_smoothing: f64) -> Self {
+        Self::with_storage(n_features, n_labels, laplace_smoothing, CountStorage::Dense)
+    }
+
+    /// Creates a new classifier with a Laplace smoothing factor for every feature, overriding the
+    /// scalar smoothing factor for the feature likelihoods as
+    /// [`Self::set_feature_laplace_factors`]. There must be exactly one factor per feature.
+    pub fn with_feature_laplace_factors(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        feature_laplace_factors: Box<[f64]>,
+    ) -> Self {
+        let mut classifier = Self::new(n_features, n_labels, laplace_smoothing);
+        classifier.set_feature_laplace_factors(feature_laplace_factors);
+        classifier
+    }
+
+    /// Creates a new classifier storing its feature counts using the specified storage. See
+    /// [`CountStorage::DenseU32`] for the restrictions of integer counts.
+    pub fn with_storage(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        storage: CountStorage,
+    ) -> Self {
+        Self {
+            feature_counts: FeatureCounts::new(storage, n_labels, n_features),
+            n_features,
+            total_samples: 0.0,
+            target_counts: vec![0.0; n_labels].into_boxed_slice(),
+            laplace_factor: laplace_smoothing,
+            class_prior: ClassPrior::default(),
+            class_weight: ClassWeight::default(),
+            smoothing: Smoothing::default(),
+            feature_laplace_factors: None,
+            binarize_threshold: 1,
+            decision_threshold: None,
+            class_names: None,
+            tie_break: TieBreak::default(),
+            unk_token: None,
+            log_prob_tables: None,
+        }
+    }
+
+    /// Loads a tokenizer from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves a tokenizer to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// The number of features the classifier is currently sized for.
+    #[inline]
+    pub fn n_features(&self) -> usize {
+        self.n_features
+    }
+
+    /// The number of target labels the classifier is currently sized for.
+    #[inline]
+    pub fn n_labels(&self) -> usize {
+        self.target_counts.len()
+    }
+
+    /// Sets the probability of label 1 above which two-label models predict it, instead of the
+    /// label with the highest probability. Has no effect on models with more labels.
+    pub fn set_decision_threshold(&mut self, decision_threshold: Option<f64>) {
+        validate_decision_threshold(decision_threshold);
+        self.decision_threshold = decision_threshold;
+    }
+
+    /// Sets the human-readable names of the target labels, e.g. from a
+    /// [`crate::labels::LabelEncoder`]. There must be exactly one name per target label.
+    pub fn set_class_names(&mut self, class_names: Box<[Box<str>]>) {
+        assert_eq!(
+            class_names.len(),
+            self.n_labels(),
+            "There must be exactly one class name per target label"
+        );
+        self.class_names = Some(class_names);
+    }
+
+    /// Sets how ties between labels are broken when predicting.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    /// Sets the index of the token the tokenizer maps unknown words to (see
+    /// [`Tokenizer::reserve_unk`]). This token is scored with the smoothed probability of a token
+    /// never seen for the label, ignoring its fitted counts.
+    pub fn set_unk_token(&mut self, unk_token: Option<usize>) {
+        assert!(
+            unk_token.is_none_or(|token| token < self.n_features),
+            "Token is out of range"
+        );
+        self.unk_token = unk_token;
+        self.log_prob_tables = None;
+    }
+
+    /// Grows the classifier to accommodate `n_features` features. Never shrinks the classifier.
+    pub fn grow_features(&mut self, n_features: usize) {
+        self.n_features = self.n_features.max(n_features);
+        self.log_prob_tables = None;
+        if let Some(factors) = &mut self.feature_laplace_factors {
+            factors.grow(self.n_features, self.laplace_factor);
+        }
+        let n_labels = self.n_labels();
+        self.feature_counts.grow(n_labels, self.n_features);
+    }
+
+    /// Grows the classifier to accommodate `n_labels` target labels. Never shrinks the classifier.
+    pub fn grow_labels(&mut self, n_labels: usize) {
+        self.class_prior.validate_growth(n_labels);
+        self.log_prob_tables = None;
+        self.feature_counts.grow(n_labels, self.n_features);
+        grow_slice(&mut self.target_counts, n_labels);
+    }
+
+    /// Sets how the label priors are estimated. Custom priors must have one prior per target label,
+    /// and the classifier then can't grow to more labels.
+    pub fn set_class_prior(&mut self, class_prior: ClassPrior) {
+        class_prior.validate(self.n_labels());
+        self.class_prior = class_prior;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets how much each label contributes to the estimates.
+    pub fn set_class_weight(&mut self, class_weight: ClassWeight) {
+        if let ClassWeight::Custom(weights) = &class_weight {
+            assert!(
+                weights.iter().all(|&w| w > 0.0),
+                "Class weights must be positive"
+            );
+        }
+        self.class_weight = class_weight;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets how the feature probabilities are smoothed.
+    pub fn set_smoothing(&mut self, smoothing: Smoothing) {
+        smoothing.validate();
+        self.smoothing = smoothing;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets a Laplace smoothing factor for every feature, overriding the scalar smoothing factor for
+    /// the feature likelihoods. Features added when growing the classifier use the scalar factor.
+    pub fn set_feature_laplace_factors(&mut self, factors: Box<[f64]>) {
+        assert_eq!(
+            factors.len(),
+            self.n_features,
+            "There must be exactly one smoothing factor per feature"
+        );
+        self.feature_laplace_factors = Some(FeatureLaplaceFactors::new(factors));
+        self.log_prob_tables = None;
+    }
+
+    /// Returns the Laplace smoothing factor of the token.
+    #[inline]
+    fn feature_laplace_factor(&self, token: usize) -> f64 {
+        self.feature_laplace_factors
+            .as_ref()
+            .map_or(self.laplace_factor, |factors| factors.factors[token])
+    }
+
+    /// Sets the minimal number of occurrences for a token to be considered present in a document.
+    /// Should be set before fitting.
+    pub fn set_binarize_threshold(&mut self, threshold: usize) {
+        assert!(threshold > 0, "Binarization threshold must be positive");
+        self.binarize_threshold = threshold;
+        self.log_prob_tables = None;
+    }
+
+    /// Returns the sorted list of tokens present in the tokenized text, that is occurring at least
+    /// as many times as the binarization threshold.
+    fn binarize(&self, tokens: &[usize]) -> Vec<usize> {
+        let mut sorted = tokens.to_vec();
+        sorted.sort_unstable();
+
+        sorted
+            .chunk_by(|a, b| a == b)
+            .filter(|occurrences| occurrences.len() >= self.binarize_threshold)
+            .map(|occurrences| occurrences[0])
+            .collect()
+    }
+
+    /// Fits the classifier on the specified tokenized text, weighting its contribution to the counts.
+    pub fn fit_weighted(&mut self, tokens: &[usize], label: usize, weight: f64) {
+        assert!(weight >= 0.0, "Sample weight must be non-negative");
+        self.feature_counts.validate_value(weight);
+        self.grow_labels(label + 1);
+        self.accumulate(tokens, label, weight);
+    }
+
+    /// Removes the contribution of a previously fitted tokenized text from the classifier.
+    pub fn unfit(&mut self, tokens: &[usize], label: usize) {
+        self.unfit_weighted(tokens, label, 1.0);
+    }
+
+    /// Removes the contribution of a tokenized text previously fitted with the same weight using
+    /// [`Self::fit_weighted`] from the classifier.
+    pub fn unfit_weighted(&mut self, tokens: &[usize], label: usize, weight: f64) {
+        assert!(weight >= 0.0, "Sample weight must be non-negative");
+        self.feature_counts.validate_value(weight);
+        // Tolerates the rounding errors accumulated by fractional weights.
+        assert!(
+            label < self.n_labels() && self.target_counts[label] >= weight - 1e-9,
+            "Can't unfit more than the fitted samples of a label"
+        );
+        let present = self.binarize(tokens);
+        assert!(
+            present.iter().all(|&token| token < self.n_features
+                && self.feature_counts.get(label, token) >= weight - 1e-9),
+            "Can't unfit more than the fitted occurrences of a token"
+        );
+        self.accumulate_present(present, label, -weight);
+    }
+
+    /// Adds the counts of another classifier trained on a different set of samples to this one.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.n_features, other.n_features,
+            "Merged classifiers must have the same number of features"
+        );
+        assert_eq!(
+            self.n_labels(),
+            other.n_labels(),
+            "Merged classifiers must have the same number of labels"
+        );
+
+        other
+            .feature_counts
+            .iter()
+            .for_each(|(label, token, count)| self.feature_counts.add(label, token, count));
+        self.target_counts
+            .iter_mut()
+            .zip(other.target_counts.iter())
+            .for_each(|(count, other)| *count += other);
+        self.total_samples += other.total_samples;
+        self.log_prob_tables = None;
+    }
+
+    /// Adds the weighted tokenized text to the counts of the label.
+    fn accumulate(&mut self, tokens: &[usize], label: usize, weight: f64) {
+        self.accumulate_present(self.binarize(tokens), label, weight);
+    }
+
+    /// Adds the weighted present tokens to the counts of the label.
+    fn accumulate_present(
+        &mut self,
+        present: impl IntoIterator<Item = usize>,
+        label: usize,
+        weight: f64,
+    ) {
+        for token in present {
+            self.feature_counts.add(label, token, weight);
+        }
+
+        self.log_prob_tables = None;
+        self.total_samples += weight;
+        self.target_counts[label] += weight;
+    }
+
+    /// Fits the classifier on the presence bitmap of a text, e.g. from
+    /// [`crate::tokenizer::Tokenizer::tokenize_bitset`]. Every set token is present, regardless of
+    /// the binarization threshold.
+    pub fn fit_bitset(&mut self, present: &FixedBitSet, label: usize) {
+        self.grow_labels(label + 1);
+        self.accumulate_present(present.ones(), label, 1.0);
+    }
+
+    /// Predicts the target label for the presence bitmap of a text.
+    pub fn predict_bitset(&self, present: &FixedBitSet) -> usize {
+        decide(
+            &self.predict_probas_bitset(present),
+            self.decision_threshold,
+            self.tie_break,
+            |label| {
+                let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+                self.label_log_prior(label, &weights, weighted_samples)
+            },
+        )
+    }
+
+    /// Returns the target label probabilities for the presence bitmap of a text, normalized to sum
+    /// to 1.
+    pub fn predict_probas_bitset(&self, present: &FixedBitSet) -> Box<[f64]> {
+        normalize_log_likelihoods(&self.joint_log_likelihood_bitset(present))
+    }
+
+    /// Returns the target label joint log-likelihoods for the presence bitmap of a text.
+    pub fn joint_log_likelihood_bitset(&self, present: &FixedBitSet) -> Box<[f64]> {
+        self.present_log_likelihood(&present.ones().collect::<Vec<_>>())
+    }
+
+    /// Returns the target label joint log-likelihoods for the sorted present tokens of a text.
+    fn present_log_likelihood(&self, present: &[usize]) -> Box<[f64]> {
+        if let Some(tables) = &self.log_prob_tables {
+            return tables.joint_log_likelihood(present.iter().map(|&token| (token, 1.0)));
+        }
+
+        let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+
+        (0..self.n_labels())
+            .map(|tgt| {
+                let mut prob = 0.0;
+                for &token in present {
+                    if token >= self.n_features {
+                        continue;
+                    }
+
+                    prob += self.token_log_prob(tgt, token, &weights);
+                }
+                prob + self.label_log_prior(tgt, &weights, weighted_samples)
+            })
+            .collect::<Vec<f64>>()
+            .into_boxed_slice()
+    }
+
+    /// Drops the rare features from the trained classifier, returning the remapping of the kept
+    /// features which must be applied to the tokenizer using [`Tokenizer::remap`].
+    pub fn prune(&mut self, pruning: Pruning) -> FeatureRemap {
+        let mut feature_totals = vec![0.0; self.n_features];
+        self.feature_counts
+            .iter()
+            .for_each(|(_, token, count)| feature_totals[token] += count);
+
+        let remap = pruning.select(&feature_totals);
+        self.remap_features(&remap);
+        remap
+    }
+
+    /// Drops and reindexes the features of the classifier according to the remapping.
+    pub fn remap_features(&mut self, remap: &FeatureRemap) {
+        assert_eq!(
+            remap.old_n_features(),
+            self.n_features,
+            "Remapping must cover all the classifier features"
+        );
+
+        self.feature_counts = self.feature_counts.remap(self.n_labels(), remap);
+        self.feature_laplace_factors = self
+            .feature_laplace_factors
+            .as_ref()
+            .map(|factors| factors.remap(remap));
+        self.unk_token = self.unk_token.and_then(|token| remap.get(token));
+        self.n_features = remap.n_features();
+        self.log_prob_tables = None;
+    }
+
+    /// Precomputes the log-probabilities used for inference, making predictions a table lookup.
+    /// The precomputed tables are discarded as soon as the classifier is modified.
+    pub fn finalize(&mut self) {
+        self.log_prob_tables = Some(self.compute_log_prob_tables());
+    }
+
+    /// Converts the classifier into a compact inference-only classifier using f32 log-probabilities.
+    pub fn to_compact(&self) -> CompactNB {
+        CompactNB::from_tables(
+            &self.compute_log_prob_tables(),
+            self.decision_threshold,
+            self.tie_break,
+            TokenScoring::Presence {
+                binarize_threshold: self.binarize_threshold,
+            },
+        )
+    }
+
+    /// Returns the `n` most informative tokens of the label, ranked by decreasing log-likelihood ratio
+    /// between the label and its most likely competing label.
+    pub fn top_features(&self, label: usize, n: usize) -> Vec<(usize, f64)> {
+        self.tables().top_features(label, n)
+    }
+
+    /// Returns the `n` most informative words of the label, as [`Self::top_features`] mapped back to
+    /// their text using the tokenizer the classifier was trained with.
+    pub fn top_words<'a>(
+        &self,
+        tokenizer: &'a Tokenizer,
+        label: usize,
+        n: usize,
+    ) -> Vec<(&'a str, f64)> {
+        self.top_features(label, n)
+            .into_iter()
+            .filter_map(|(token, ratio)| tokenizer.token(token).map(|word| (word, ratio)))
+            .collect()
+    }
+
+    /// Returns the contribution of every present token of the tokenized text to the joint
+    /// log-likelihood of each label, explaining why a text was classified as it was.
+    pub fn explain(&self, tokens: &[usize]) -> Vec<(usize, Box<[f64]>)> {
+        let (weights, _) = self.class_weight.weights(&self.target_counts);
+
+        self.binarize(tokens)
+            .into_iter()
+            .filter(|&token| token < self.n_features)
+            .map(|token| {
+                let contributions = (0..self.n_labels())
+                    .map(|tgt| self.token_log_prob(tgt, token, &weights))
+                    .collect();
+                (token, contributions)
+            })
+            .collect()
+    }
+
+    /// Returns the weighted number of samples of the label containing the token.
+    pub fn feature_count(&self, label: usize, token: usize) -> f64 {
+        assert!(label < self.n_labels(), "Label is out of range");
+        assert!(token < self.n_features, "Token is out of range");
+        self.feature_counts.get(label, token)
+    }
+
+    /// Returns the log prior probability of each label.
+    pub fn class_log_prior(&self) -> Box<[f64]> {
+        self.tables().class_log_prior.clone()
+    }
+
+    /// Returns the log probability of each token given each label, indexed as
+    /// `label * n_features + token`.
+    pub fn feature_log_prob(&self) -> Box<[f64]> {
+        self.tables().feature_log_prob.clone()
+    }
+
+    /// Exports the log prior and token log probabilities of each label as CSV, naming the tokens
+    /// using the tokenizer the classifier was trained with, if any.
+    pub fn save_log_prob_csv(
+        &self,
+        file: &mut dyn std::io::Write,
+        tokenizer: Option<&Tokenizer>,
+    ) -> std::io::Result<()> {
+        self.tables()
+            .write_csv(file, self.class_names.as_deref(), tokenizer)
+    }
+
+    /// Returns the precomputed log-probabilities if finalized, or computes them.
+    fn tables(&self) -> Cow<'_, LogProbTables> {
+        match &self.log_prob_tables {
+            Some(tables) => Cow::Borrowed(tables),
+            None => Cow::Owned(self.compute_log_prob_tables()),
+        }
+    }
+
+    /// Computes the log-probabilities used for inference from the fitted counts.
+    fn compute_log_prob_tables(&self) -> LogProbTables {
+        let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+
+        LogProbTables {
+            class_log_prior: (0..self.n_labels())
+                .map(|tgt| self.label_log_prior(tgt, &weights, weighted_samples))
+                .collect(),
+            feature_log_prob: (0..self.n_labels())
+                .flat_map(|tgt| (0..self.n_features).map(move |token| (tgt, token)))
+                .map(|(tgt, token)| self.token_log_prob(tgt, token, &weights))
+                .collect(),
+            n_features: self.n_features,
+        }
+    }
+
+    /// Whether the classifier log-probabilities are currently precomputed.
+    #[inline]
+    pub fn is_finalized(&self) -> bool {
+        self.log_prob_tables.is_some()
+    }
+
+    /// Returns the log prior probability of the label.
+    fn label_log_prior(&self, label: usize, weights: &[f64], weighted_samples: f64) -> f64 {
+        self.class_prior.log_prior(
+            label,
+            self.target_counts[label] * weights[label],
+            weighted_samples,
+            self.n_labels(),
+            self.laplace_factor,
+        )
+    }
+
+    /// Returns the fitted count of the token for the label, zero for the unknown word token.
+    fn scored_count(&self, label: usize, token: usize) -> f64 {
+        if Some(token) == self.unk_token {
+            0.0
+        } else {
+            self.feature_counts.get(label, token)
+        }
+    }
+
+    /// Returns the smoothed log probability of the token given the label.
+    fn token_log_prob(&self, label: usize, token: usize, weights: &[f64]) -> f64 {
+        self.smoothing
+            .probability(
+                self.scored_count(label, token) * weights[label],
+                self.target_counts[label] * weights[label],
+                self.feature_counts.distinct(label),
+                self.feature_laplace_factor(token),
+                self.n_labels() as f64 * self.feature_laplace_factor(token),
+                || self.corpus_feature_prob(token, weights),
+            )
+            .ln()
+    }
+
+    /// Returns the Laplace smoothed fraction of the whole fitted corpus samples containing the token.
+    fn corpus_feature_prob(&self, token: usize, weights: &[f64]) -> f64 {
+        let feature_count: f64 = (0..self.n_labels())
+            .map(|label| self.scored_count(label, token) * weights[label])
+            .sum();
+        let total_samples: f64 = self
+            .target_counts
+            .iter()
+            .zip(weights.iter())
+            .map(|(count, weight)| count * weight)
+            .sum();
+
+        (feature_count + self.laplace_factor) / (total_samples + 2.0 * self.laplace_factor)
+    }
+
+    /// Fits the classifier on the specified tokenized text, first growing it to accommodate tokens
+    /// beyond its current number of features (e.g. newly learned by the tokenizer).
+    pub fn partial_fit(&mut self, tokens: &[usize], label: usize) {
+        if let Some(&max_token) = tokens.iter().max() {
+            self.grow_features(max_token + 1);
+        }
+        self.fit(tokens, label);
+    }
+}
+
+impl NaiveBayesClassifier for BernouliNB {
+    /// Fits the classifier on the specified tokenized text.
+    fn fit(&mut self, tokens: &[usize], label: usize) {
+        self.fit_weighted(tokens, label, 1.0);
+    }
+
+    /// Predicts the target label for the sparse tokenized text
+    fn predict(&self, tokens: &[usize]) -> usize {
+        decide(
+            &self.predict_probas(tokens),
+            self.decision_threshold,
+            self.tie_break,
+            |label| {
+                let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+                self.label_log_prior(label, &weights, weighted_samples)
+            },
+        )
+    }
+
+    /// Returns the human-readable names of the target labels, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.class_names.as_deref()
+    }
+
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.present_log_likelihood(&self.binarize(tokens))
+    }
+}
+
+/// A Naive Bayes classifier using multinomial features (word frequency).
+#[derive(Serialize, Deserialize)]
+pub struct MultinomialNB {
+    /// Weighted feature counts for each label.
+    feature_counts: FeatureCounts,
+    /// Number of features.
+    n_features: usize,
+    /// Weighted total feature counts per label.
+    label_feature_totals: Box<[f64]>,
+    /// Count of target labels.
+    target_counts: Box<[f64]>,
+    /// The Laplace smoothing factor.
+    laplace_factor: f64,
+    /// Total number of samples.
+    total_samples: f64,
+    /// How the label priors are estimated.
+    class_prior: ClassPrior,
+    /// How much each label contributes to the estimates.
+    class_weight: ClassWeight,
+    /// Precomputed log-probabilities, if the classifier has been finalized.
+    #[serde(skip)]
+    log_prob_tables: Option<LogProbTables>,
+    /// How the feature probabilities are smoothed.
+    smoothing: Smoothing,
+    /// Per-feature Laplace smoothing factors overriding the scalar one, if any.
+    feature_laplace_factors: Option<FeatureLaplaceFactors>,
+    /// Whether token frequencies are dampened as `1 + ln(tf)`.
+    sublinear_tf: bool,
+    /// Maximal frequency a token can have in a single document, if any.
+    max_tf: Option<usize>,
+    /// Probability of label 1 above which two-label models predict it, instead of the argmax.
+    decision_threshold: Option<f64>,
+    /// Human-readable names of the target labels, if any.
+    class_names: Option<Box<[Box<str>]>>,
+    /// How ties between labels are broken when predicting.
+    tie_break: TieBreak,
+    /// Index of the token unknown words are mapped to, scored as a token never seen, if any.
+    unk_token: Option<usize>,
+}
+
+impl MultinomialNB {
+    pub fn new(n_features: usize, n_labels: usize, laplace_smoothing: f64) -> Self {
+        Self::with_storage(n_features, n_labels, laplace_smoothing, CountStorage::Dense)
+    }
+
+    /// Creates a new classifier with a Laplace smoothing factor for every feature, overriding the
+    /// scalar smoothing factor for the feature likelihoods as
+    /// [`Self::set_feature_laplace_factors`]. There must be exactly one factor per feature.
+    pub fn with_feature_laplace_factors(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        feature_laplace_factors: Box<[f64]>,
+    ) -> Self {
+        let mut classifier = Self::new(n_features, n_labels, laplace_smoothing);
+        classifier.set_feature_laplace_factors(feature_laplace_factors);
+        classifier
+    }
+
+    /// Creates a new classifier storing its feature counts using the specified storage. See
+    /// [`CountStorage::DenseU32`] for the restrictions of integer counts.
+    pub fn with_storage(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        storage: CountStorage,
+    ) -> Self {
+        Self {
+            feature_counts: FeatureCounts::new(storage, n_labels, n_features),
+            n_features,
+            label_feature_totals: vec![0.0; n_labels].into_boxed_slice(),
+            total_samples: 0.0,
+            target_counts: vec![0.0; n_labels].into_boxed_slice(),
+            laplace_factor: laplace_smoothing,
+            class_prior: ClassPrior::default(),
+            class_weight: ClassWeight::default(),
+            smoothing: Smoothing::default(),
+            feature_laplace_factors: None,
+            sublinear_tf: false,
+            max_tf: None,
+            decision_threshold: None,
+            class_names: None,
+            tie_break: TieBreak::default(),
+            unk_token: None,
+            log_prob_tables: None,
+        }
+    }
+
+    /// The number of features the classifier is currently sized for.
+    #[inline]
+    pub fn n_features(&self) -> usize {
+        self.n_features
+    }
+
+    /// The number of target labels the classifier is currently sized for.
+    #[inline]
+    pub fn n_labels(&self) -> usize {
+        self.target_counts.len()
+    }
+
+    /// Sets the probability of label 1 above which two-label models predict it, instead of the
+    /// label with the highest probability. Has no effect on models with more labels.
+    pub fn set_decision_threshold(&mut self, decision_threshold: Option<f64>) {
+        validate_decision_threshold(decision_threshold);
+        self.decision_threshold = decision_threshold;
+    }
+
+    /// Sets the human-readable names of the target labels, e.g. from a
+    /// [`crate::labels::LabelEncoder`]. There must be exactly one name per target label.
+    pub fn set_class_names(&mut self, class_names: Box<[Box<str>]>) {
+        assert_eq!(
+            class_names.len(),
+            self.n_labels(),
+            "There must be exactly one class name per target label"
+        );
+        self.class_names = Some(class_names);
+    }
+
+    /// Sets how ties between labels are broken when predicting.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    /// Sets the index of the token the tokenizer maps unknown words to (see
+    /// [`Tokenizer::reserve_unk`]). This token is scored with the smoothed probability of a token
+    /// never seen for the label, ignoring its fitted counts.
+    pub fn set_unk_token(&mut self, unk_token: Option<usize>) {
+        assert!(
+            unk_token.is_none_or(|token| token < self.n_features),
+            "Token is out of range"
+        );
+        self.unk_token = unk_token;
+        self.log_prob_tables = None;
+    }
+
+    /// Grows the classifier to accommodate `n_features` features. Never shrinks the classifier.
+    pub fn grow_features(&mut self, n_features: usize) {
+        self.n_features = self.n_features.max(n_features);
+        self.log_prob_tables = None;
+        if let Some(factors) = &mut self.feature_laplace_factors {
+            factors.grow(self.n_features, self.laplace_factor);
+        }
+        let n_labels = self.n_labels();
+        self.feature_counts.grow(n_labels, self.n_features);
+    }
+
+    /// Grows the classifier to accommodate `n_labels` target labels. Never shrinks the classifier.
+    pub fn grow_labels(&mut self, n_labels: usize) {
+        self.class_prior.validate_growth(n_labels);
+        self.log_prob_tables = None;
+        self.feature_counts.grow(n_labels, self.n_features);
+        grow_slice(&mut self.target_counts, n_labels);
+        grow_slice(&mut self.label_feature_totals, n_labels);
+    }
+
+    /// Sets how the label priors are estimated. Custom priors must have one prior per target label,
+    /// and the classifier then can't grow to more labels.
+    pub fn set_class_prior(&mut self, class_prior: ClassPrior) {
+        class_prior.validate(self.n_labels());
+        self.class_prior = class_prior;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets how much each label contributes to the estimates.
+    pub fn set_class_weight(&mut self, class_weight: ClassWeight) {
+        if let ClassWeight::Custom(weights) = &class_weight {
+            assert!(
+                weights.iter().all(|&w| w > 0.0),
+                "Class weights must be positive"
+            );
+        }
+        self.class_weight = class_weight;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets how the feature probabilities are smoothed.
+    pub fn set_smoothing(&mut self, smoothing: Smoothing) {
+        smoothing.validate();
+        self.smoothing = smoothing;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets a Laplace smoothing factor for every feature, overriding the scalar smoothing factor for
+    /// the feature likelihoods. Features added when growing the classifier use the scalar factor.
+    pub fn set_feature_laplace_factors(&mut self, factors: Box<[f64]>) {
+        assert_eq!(
+            factors.len(),
+            self.n_features,
+            "There must be exactly one smoothing factor per feature"
+        );
+        self.feature_laplace_factors = Some(FeatureLaplaceFactors::new(factors));
+        self.log_prob_tables = None;
+    }
+
+    /// Returns the Laplace smoothing factor of the token.
+    #[inline]
+    fn feature_laplace_factor(&self, token: usize) -> f64 {
+        self.feature_laplace_factors
+            .as_ref()
+            .map_or(self.laplace_factor, |factors| factors.factors[token])
+    }
+
+    /// Sets whether token frequencies are dampened as `1 + ln(tf)` when fitting and predicting, so
+    /// that repeated tokens don't dominate the likelihood. Should be set before fitting.
+    ///
+    /// Panics when enabling it on a classifier storing its counts as [`CountStorage::DenseU32`],
+    /// which can't hold the resulting fractional frequencies.
+    pub fn set_sublinear_tf(&mut self, sublinear_tf: bool) {
+        assert!(
+            !sublinear_tf || self.feature_counts.storage() != CountStorage::DenseU32,
+            "Sublinear term frequencies aren't supported by 32-bit integer feature counts"
+        );
+        self.sublinear_tf = sublinear_tf;
+        self.log_prob_tables = None;
+    }
+
+    /// Sets the maximal frequency a token can have in a single document when fitting and predicting,
+    /// limiting the influence of pathologically repeated tokens. Should be set before fitting.
+    pub fn set_max_tf(&mut self, max_tf: Option<usize>) {
+        assert!(max_tf != Some(0), "Maximal term frequency must be positive");
+        self.max_tf = max_tf;
+        self.log_prob_tables = None;
+    }
+
+    /// Returns the frequency of every token of the tokenized text.
+    fn term_frequencies(&self, tokens: &[usize]) -> HashMap<usize, f64> {
+        let mut frequencies = tokens.iter().copied().fold(HashMap::new(), |mut map, val| {
+            map.entry(val)
+                .and_modify(|frq| *frq += 1.0)
+                .or_insert(1.0f64);
+            map
+        });
+
+        frequencies
+            .values_mut()
+            .for_each(|frequency| *frequency = self.scale_frequency(*frequency));
+
+        frequencies
+    }
+
+    /// Returns the frequency of every token of a sparse (token, count) vector.
+    fn count_frequencies(&self, counts: &[(usize, u32)]) -> Vec<(usize, f64)> {
+        counts
+            .iter()
+            .map(|&(token, count)| (token, self.scale_frequency(count as f64)))
+            .collect()
+    }
+
+    /// Caps the raw frequency of a token in a document and applies sublinear scaling, if enabled.
+    fn scale_frequency(&self, frequency: f64) -> f64 {
+        scale_frequency(frequency, self.max_tf, self.sublinear_tf)
+    }
+
+    /// Fits the classifier on the specified tokenized text, weighting its contribution to the counts.
+    pub fn fit_weighted(&mut self, tokens: &[usize], target: usize, weight: f64) {
+        assert!(weight >= 0.0, "Sample weight must be non-negative");
+        self.feature_counts.validate_value(weight);
+        self.grow_labels(target + 1);
+        self.accumulate(tokens, target, weight);
+    }
+
+    /// Removes the contribution of a previously fitted tokenized text from the classifier.
+    pub fn unfit(&mut self, tokens: &[usize], target: usize) {
+        self.unfit_weighted(tokens, target, 1.0);
+    }
+
+    /// Removes the contribution of a tokenized text previously fitted with the same weight using
+    /// [`Self::fit_weighted`] from the classifier.
+    pub fn unfit_weighted(&mut self, tokens: &[usize], target: usize, weight: f64) {
+        assert!(weight >= 0.0, "Sample weight must be non-negative");
+        self.feature_counts.validate_value(weight);
+        // Tolerates the rounding errors accumulated by fractional weights.
+        assert!(
+            target < self.n_labels() && self.target_counts[target] >= weight - 1e-9,
+            "Can't unfit more than the fitted samples of a label"
+        );
+        let frequencies = self.term_frequencies(tokens);
+        assert!(
+            frequencies
+                .iter()
+                .all(|(&token, &value)| token < self.n_features
+                    && self.feature_counts.get(target, token) >= value * weight - 1e-9),
+            "Can't unfit more than the fitted occurrences of a token"
+        );
+        self.accumulate_features(frequencies, target, -weight);
+    }
+
+    /// Adds the counts of another classifier trained on a different set of samples to this one.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.n_features, other.n_features,
+            "Merged classifiers must have the same number of features"
+        );
+        assert_eq!(
+            self.n_labels(),
+            other.n_labels(),
+            "Merged classifiers must have the same number of labels"
+        );
+
+        other
+            .feature_counts
+            .iter()
+            .for_each(|(label, token, count)| self.feature_counts.add(label, token, count));
+        self.target_counts
+            .iter_mut()
+            .zip(other.target_counts.iter())
+            .chain(
+                self.label_feature_totals
+                    .iter_mut()
+                    .zip(other.label_feature_totals.iter()),
+            )
+            .for_each(|(count, other)| *count += other);
+        self.total_samples += other.total_samples;
+        self.log_prob_tables = None;
+    }
+
+    /// Adds the weighted tokenized text to the counts of the label.
+    fn accumulate(&mut self, tokens: &[usize], target: usize, weight: f64) {
+        self.accumulate_features(self.term_frequencies(tokens), target, weight);
+    }
+
+    /// Adds the weighted (token, value) features to the counts of the label.
+    fn accumulate_features(
+        &mut self,
+        features: impl IntoIterator<Item = (usize, f64)>,
+        target: usize,
+        weight: f64,
+    ) {
+        for (token, value) in features {
+            self.feature_counts.add(target, token, value * weight);
+            self.label_feature_totals[target] += value * weight;
+        }
+
+        self.log_prob_tables = None;
+        self.total_samples += weight;
+        self.target_counts[target] += weight;
+    }
+
+    /// Fits the classifier on a sparse (token, count) vector, e.g. from
+    /// [`crate::tokenizer::Tokenizer::tokenize_counts`], without recounting the tokens.
+    pub fn fit_counts(&mut self, counts: &[(usize, u32)], target: usize) {
+        self.grow_labels(target + 1);
+        self.accumulate_features(self.count_frequencies(counts), target, 1.0);
+    }
+
+    /// Predicts the target label for a sparse (token, count) vector.
+    pub fn predict_counts(&self, counts: &[(usize, u32)]) -> usize {
+        decide(
+            &self.predict_probas_counts(counts),
+            self.decision_threshold,
+            self.tie_break,
+            |label| {
+                let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+                self.label_log_prior(label, &weights, weighted_samples)
+            },
+        )
+    }
+
+    /// Returns the target label probabilities for a sparse (token, count) vector, normalized to sum
+    /// to 1.
+    pub fn predict_probas_counts(&self, counts: &[(usize, u32)]) -> Box<[f64]> {
+        normalize_log_likelihoods(&self.joint_log_likelihood_counts(counts))
+    }
+
+    /// Returns the target label joint log-likelihoods for a sparse (token, count) vector.
+    pub fn joint_log_likelihood_counts(&self, counts: &[(usize, u32)]) -> Box<[f64]> {
+        self.joint_log_likelihood_features(&self.count_frequencies(counts))
+    }
+
+    /// Fits the classifier on a sparse vector of float-valued (token, value) features, such as TF-IDF
+    /// weights, instead of raw token counts.
+    ///
+    /// Classifiers storing their counts as [`CountStorage::DenseU32`] only accept integer values,
+    /// and panic on fractional ones before changing any count.
+    pub fn fit_features(&mut self, features: &[(usize, f64)], target: usize) {
+        assert!(
+            features.iter().all(|(_, value)| *value >= 0.0),
+            "Feature values must be non-negative"
+        );
+        features
+            .iter()
+            .for_each(|&(_, value)| self.feature_counts.validate_value(value));
+        self.grow_labels(target + 1);
+        self.accumulate_features(features.iter().copied(), target, 1.0);
+    }
+
+    /// Predicts the target label for a sparse vector of float-valued (token, value) features.
+    pub fn predict_features(&self, features: &[(usize, f64)]) -> usize {
+        decide(
+            &self.predict_probas_features(features),
+            self.decision_threshold,
+            self.tie_break,
+            |label| {
+                let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+                self.label_log_prior(label, &weights, weighted_samples)
+            },
+        )
+    }
+
+    /// Returns the target label probabilities for a sparse vector of float-valued (token, value)
+    /// features, normalized to sum to 1.
+    pub fn predict_probas_features(&self, features: &[(usize, f64)]) -> Box<[f64]> {
+        normalize_log_likelihoods(&self.joint_log_likelihood_features(features))
+    }
+
+    /// Returns the target label joint log-likelihoods for a sparse vector of float-valued
+    /// (token, value) features.
+    pub fn joint_log_likelihood_features(&self, features: &[(usize, f64)]) -> Box<[f64]> {
+        if let Some(tables) = &self.log_prob_tables {
+            return tables.joint_log_likelihood(features.iter().copied());
+        }
+
+        let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+
+        (0..self.n_labels())
+            .map(|tgt| {
+                let mut log_prob = self.label_log_prior(tgt, &weights, weighted_samples);
+
+                for &(token, value) in features {
+                    if token >= self.n_features {
+                        continue;
+                    }
+
+                    log_prob += value * self.token_log_prob(tgt, token, &weights);
+                }
+
+                log_prob
+            })
+            .collect::<Vec<f64>>()
+            .into_boxed_slice()
+    }
+
+    /// Drops the rare features from the trained classifier, returning the remapping of the kept
+    /// features which must be applied to the tokenizer using [`Tokenizer::remap`].
+    pub fn prune(&mut self, pruning: Pruning) -> FeatureRemap {
+        let mut feature_totals = vec![0.0; self.n_features];
+        self.feature_counts
+            .iter()
+            .for_each(|(_, token, count)| feature_totals[token] += count);
+
+        let remap = pruning.select(&feature_totals);
+        self.remap_features(&remap);
+        remap
+    }
+
+    /// Drops and reindexes the features of the classifier according to the remapping.
+    pub fn remap_features(&mut self, remap: &FeatureRemap) {
+        assert_eq!(
+            remap.old_n_features(),
+            self.n_features,
+            "Remapping must cover all the classifier features"
+        );
+
+        self.feature_counts = self.feature_counts.remap(self.n_labels(), remap);
+        self.feature_laplace_factors = self
+            .feature_laplace_factors
+            .as_ref()
+            .map(|factors| factors.remap(remap));
+        self.unk_token = self.unk_token.and_then(|token| remap.get(token));
+        self.n_features = remap.n_features();
+        self.log_prob_tables = None;
+
+        self.label_feature_totals
+            .iter_mut()
+            .for_each(|total| *total = 0.0);
+        self.feature_counts
+            .iter()
+            .for_each(|(label, _, count)| self.label_feature_totals[label] += count);
+    }
+
+    /// Precomputes the log-probabilities used for inference, making predictions a table lookup.
+    /// The precomputed tables are discarded as soon as the classifier is modified.
+    pub fn finalize(&mut self) {
+        self.log_prob_tables = Some(self.compute_log_prob_tables());
+    }
+
+    /// Converts the classifier into a compact inference-only classifier using f32 log-probabilities.
+    pub fn to_compact(&self) -> CompactNB {
+        CompactNB::from_tables(
+            &self.compute_log_prob_tables(),
+            self.decision_threshold,
+            self.tie_break,
+            TokenScoring::Frequencies {
+                sublinear_tf: self.sublinear_tf,
+                max_tf: self.max_tf,
+            },
+        )
+    }
+
+    /// Returns the `n` most informative tokens of the label, ranked by decreasing log-likelihood ratio
+    /// between the label and its most likely competing label.
+    pub fn top_features(&self, label: usize, n: usize) -> Vec<(usize, f64)> {
+        self.tables().top_features(label, n)
+    }
+
+    /// Returns the `n` most informative words of the label, as [`Self::top_features`] mapped back to
+    /// their text using the tokenizer the classifier was trained with.
+    pub fn top_words<'a>(
+        &self,
+        tokenizer: &'a Tokenizer,
+        label: usize,
+        n: usize,
+    ) -> Vec<(&'a str, f64)> {
+        self.top_features(label, n)
+            .into_iter()
+            .filter_map(|(token, ratio)| tokenizer.token(token).map(|word| (word, ratio)))
+            .collect()
+    }
+
+    /// Returns the contribution of every known token of the tokenized text to the joint
+    /// log-likelihood of each label, explaining why a text was classified as it was.
+    pub fn explain(&self, tokens: &[usize]) -> Vec<(usize, Box<[f64]>)> {
+        let (weights, _) = self.class_weight.weights(&self.target_counts);
+
+        let mut contributions: Vec<(usize, Box<[f64]>)> = self
+            .term_frequencies(tokens)
+            .into_iter()
+            .filter(|&(token, _)| token < self.n_features)
+            .map(|(token, frequency)| {
+                let contributions = (0..self.n_labels())
+                    .map(|tgt| frequency * self.token_log_prob(tgt, token, &weights))
+                    .collect();
+                (token, contributions)
+            })
+            .collect();
+
+        contributions.sort_unstable_by_key(|(token, _)| *token);
+        contributions
+    }
+
+    /// Returns the weighted number of occurrences of the token in the samples of the label.
+    pub fn feature_count(&self, label: usize, token: usize) -> f64 {
+        assert!(label < self.n_labels(), "Label is out of range");
+        assert!(token < self.n_features, "Token is out of range");
+        self.feature_counts.get(label, token)
+    }
+
+    /// Returns the log prior probability of each label.
+    pub fn class_log_prior(&self) -> Box<[f64]> {
+        self.tables().class_log_prior.clone()
+    }
+
+    /// Returns the log probability of each token given each label, indexed as
+    /// `label * n_features + token`.
+    pub fn feature_log_prob(&self) -> Box<[f64]> {
+        self.tables().feature_log_prob.clone()
+    }
+
+    /// Exports the log prior and token log probabilities of each label as CSV, naming the tokens
+    /// using the tokenizer the classifier was trained with, if any.
+    pub fn save_log_prob_csv(
+        &self,
+        file: &mut dyn std::io::Write,
+        tokenizer: Option<&Tokenizer>,
+    ) -> std::io::Result<()> {
+        self.tables()
+            .write_csv(file, self.class_names.as_deref(), tokenizer)
+    }
+
+    /// Returns the precomputed log-probabilities if finalized, or computes them.
+    fn tables(&self) -> Cow<'_, LogProbTables> {
+        match &self.log_prob_tables {
+            Some(tables) => Cow::Borrowed(tables),
+            None => Cow::Owned(self.compute_log_prob_tables()),
+        }
+    }
+
+    /// Computes the log-probabilities used for inference from the fitted counts.
+    fn compute_log_prob_tables(&self) -> LogProbTables {
+        let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+
+        LogProbTables {
+            class_log_prior: (0..self.n_labels())
+                .map(|tgt| self.label_log_prior(tgt, &weights, weighted_samples))
+                .collect(),
+            feature_log_prob: (0..self.n_labels())
+                .flat_map(|tgt| (0..self.n_features).map(move |token| (tgt, token)))
+                .map(|(tgt, token)| self.token_log_prob(tgt, token, &weights))
+                .collect(),
+            n_features: self.n_features,
+        }
+    }
+
+    /// Whether the classifier log-probabilities are currently precomputed.
+    #[inline]
+    pub fn is_finalized(&self) -> bool {
+        self.log_prob_tables.is_some()
+    }
+
+    /// Returns the log prior probability of the label.
+    fn label_log_prior(&self, label: usize, weights: &[f64], weighted_samples: f64) -> f64 {
+        self.class_prior.log_prior(
+            label,
+            self.target_counts[label] * weights[label],
+            weighted_samples,
+            self.n_labels(),
+            self.laplace_factor,
+        )
+    }
+
+    /// Returns the fitted count of the token for the label, zero for the unknown word token.
+    fn scored_count(&self, label: usize, token: usize) -> f64 {
+        if Some(token) == self.unk_token {
+            0.0
+        } else {
+            self.feature_counts.get(label, token)
+        }
+    }
+
+    /// Returns the smoothed log probability of the token given the label.
+    fn token_log_prob(&self, label: usize, token: usize, weights: &[f64]) -> f64 {
+        self.smoothing
+            .probability(
+                self.scored_count(label, token) * weights[label],
+                self.label_feature_totals[label] * weights[label],
+                self.feature_counts.distinct(label),
+                self.feature_laplace_factor(token),
+                self.feature_laplace_factors
+                    .as_ref()
+                    .map_or(self.n_features as f64 * self.laplace_factor, |factors| {
+                        factors.total
+                    }),
+                || self.corpus_feature_prob(token, weights),
+            )
+            .ln()
+    }
+
+    /// Returns the Laplace smoothed probability of the token over the whole fitted corpus.
+    fn corpus_feature_prob(&self, token: usize, weights: &[f64]) -> f64 {
+        let feature_count: f64 = (0..self.n_labels())
+            .map(|label| self.scored_count(label, token) * weights[label])
+            .sum();
+        let total_features: f64 = self
+            .label_feature_totals
+            .iter()
+            .zip(weights.iter())
+            .map(|(total, weight)| total * weight)
+            .sum();
+
+        (feature_count + self.laplace_factor)
+            / (total_features + self.n_features as f64 * self.laplace_factor)
+    }
+
+    /// Fits the classifier on the specified tokenized text, first growing it to accommodate tokens
+    /// beyond its current number of features (e.g. newly learned by the tokenizer).
+    pub fn partial_fit(&mut self, tokens: &[usize], target: usize) {
+        if let Some(&max_token) = tokens.iter().max() {
+            self.grow_features(max_token + 1);
+        }
+        self.fit(tokens, target);
+    }
 }
 
-/// A Naive Bayes classifier using binary features (presence or absence of a specific word).
+impl DecrementalClassifier for BernouliNB {
+    fn unfit(&mut self, tokens: &[usize], label: usize) {
+        BernouliNB::unfit(self, tokens, label);
+    }
+}
+
+impl NaiveBayesClassifier for MultinomialNB {
+    /// Predicts the target label for the tokenized text
+    fn predict(&self, tokens: &[usize]) -> usize {
+        decide(
+            &self.predict_probas(tokens),
+            self.decision_threshold,
+            self.tie_break,
+            |label| {
+                let (weights, weighted_samples) = self.class_weight.weights(&self.target_counts);
+                self.label_log_prior(label, &weights, weighted_samples)
+            },
+        )
+    }
+
+    /// Returns the human-readable names of the target labels, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.class_names.as_deref()
+    }
+
+    /// Fits the classifier on the specified tokenized text.
+    fn fit(&mut self, tokens: &[usize], target: usize) {
+        self.fit_weighted(tokens, target, 1.0);
+    }
+
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        let features: Vec<(usize, f64)> = self.term_frequencies(tokens).into_iter().collect();
+        self.joint_log_likelihood_features(&features)
+    }
+}
+
+impl DecrementalClassifier for MultinomialNB {
+    fn unfit(&mut self, tokens: &[usize], label: usize) {
+        MultinomialNB::unfit(self, tokens, label);
+    }
+}
+
+/// A multinomial Naive Bayes classifier whose counts decay exponentially as new samples are fitted,
+/// so that older evidence progressively fades out on drifting data streams.
+///
+/// Counts are stored relative to an ever-growing sample weight instead of being decayed in place,
+/// making `fit` independent of the model size.
 #[derive(Serialize, Deserialize)]
-pub struct BernouliNB {
-    /// Feature counts for each label.
-    feature_counts: Box<[Box<[usize]>]>,
-    /// Total number of samples
-    total_samples: usize,
-    /// Count of target labels
-    target_counts: Box<[usize]>,
-    /// The Laplace smoothing factor
+pub struct DecayingNB {
+    /// Weighted feature counts for each label.
+    feature_counts: Box<[Box<[f64]>]>,
+    /// Number of features.
+    n_features: usize,
+    /// Weighted total feature counts per label.
+    label_feature_totals: Box<[f64]>,
+    /// Weighted count of target labels.
+    target_counts: Box<[f64]>,
+    /// Weighted total number of samples.
+    total_samples: f64,
+    /// The Laplace smoothing factor.
     laplace_factor: f64,
+    /// The factor by which previous evidence is multiplied every time a new sample is fitted.
+    decay: f64,
+    /// The weight given to the next fitted sample.
+    sample_weight: f64,
+    /// Probability of label 1 above which two-label models predict it, instead of the argmax.
+    decision_threshold: Option<f64>,
+    /// Human-readable names of the target labels, if any.
+    class_names: Option<Box<[Box<str>]>>,
+    /// How ties between labels are broken when predicting.
+    tie_break: TieBreak,
 }
 
-impl BernouliNB {
-    pub fn new(n_features: usize, n_labels: usize, laplace_smoothing: f64) -> Self {
+impl DecayingNB {
+    /// Threshold above which the stored counts are rescaled to avoid overflowing.
+    const RESCALE_THRESHOLD: f64 = 1e100;
+
+    pub fn new(n_features: usize, n_labels: usize, laplace_smoothing: f64, decay: f64) -> Self {
+        assert!(
+            decay > 0.0 && decay <= 1.0,
+            "Decay factor must be in the ]0, 1] range"
+        );
+
         Self {
-            feature_counts: vec![vec![0; n_features].into_boxed_slice(); n_labels]
+            feature_counts: vec![vec![0.0; n_features].into_boxed_slice(); n_labels]
                 .into_boxed_slice(),
-            total_samples: 0,
-            target_counts: vec![0; n_labels].into_boxed_slice(),
+            n_features,
+            label_feature_totals: vec![0.0; n_labels].into_boxed_slice(),
+            target_counts: vec![0.0; n_labels].into_boxed_slice(),
+            total_samples: 0.0,
             laplace_factor: laplace_smoothing,
+            decay,
+            sample_weight: 1.0,
+            decision_threshold: None,
+            class_names: None,
+            tie_break: TieBreak::default(),
         }
     }
 
-    /// Loads a tokenizer from a file.
-    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer)
-            .map_err(serde_json::Error::custom)?;
-        serde_json::from_str(&buffer)
+    /// Divides all the stored counts by the current sample weight and resets it.
+    fn rescale(&mut self) {
+        let scale = self.sample_weight;
+        self.feature_counts
+            .iter_mut()
+            .flat_map(|counts| counts.iter_mut())
+            .chain(self.label_feature_totals.iter_mut())
+            .chain(self.target_counts.iter_mut())
+            .chain(std::iter::once(&mut self.total_samples))
+            .for_each(|count| *count /= scale);
+        self.sample_weight = 1.0;
     }
 
-    /// Saves a tokenizer to a file.
-    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
-        let serialized = serde_json::to_string(self)?;
-        file.write_all(serialized.as_bytes())
-            .map_err(serde_json::Error::custom)
+    /// The number of features the classifier is currently sized for.
+    #[inline]
+    pub fn n_features(&self) -> usize {
+        self.n_features
     }
-}
 
-impl NaiveBayesClassifier for BernouliNB {
-    /// Fits the classifier on the specified tokenized text.
-    fn fit(&mut self, tokens: &[usize], label: usize) {
-        assert!(label < self.target_counts.len());
+    /// The number of target labels the classifier is currently sized for.
+    #[inline]
+    pub fn n_labels(&self) -> usize {
+        self.target_counts.len()
+    }
+
+    /// Sets the probability of label 1 above which two-label models predict it, instead of the
+    /// label with the highest probability. Has no effect on models with more labels.
+    pub fn set_decision_threshold(&mut self, decision_threshold: Option<f64>) {
+        validate_decision_threshold(decision_threshold);
+        self.decision_threshold = decision_threshold;
+    }
+
+    /// Sets the human-readable names of the target labels, e.g. from a
+    /// [`crate::labels::LabelEncoder`]. There must be exactly one name per target label.
+    pub fn set_class_names(&mut self, class_names: Box<[Box<str>]>) {
+        assert_eq!(
+            class_names.len(),
+            self.n_labels(),
+            "There must be exactly one class name per target label"
+        );
+        self.class_names = Some(class_names);
+    }
+
+    /// Sets how ties between labels are broken when predicting.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    /// Grows the classifier to accommodate `n_features` features. Never shrinks the classifier.
+    pub fn grow_features(&mut self, n_features: usize) {
+        self.n_features = self.n_features.max(n_features);
+        let n_labels = self.n_labels();
+        grow_feature_rows(&mut self.feature_counts, n_labels, self.n_features);
+    }
+
+    /// Grows the classifier to accommodate `n_labels` target labels. Never shrinks the classifier.
+    pub fn grow_labels(&mut self, n_labels: usize) {
+        grow_feature_rows(&mut self.feature_counts, n_labels, self.n_features);
+        grow_slice(&mut self.target_counts, n_labels);
+        grow_slice(&mut self.label_feature_totals, n_labels);
+    }
+
+    /// Fits the classifier on the specified tokenized text, weighting its contribution to the counts
+    /// and decaying previously fitted samples.
+    pub fn fit_weighted(&mut self, tokens: &[usize], target: usize, weight: f64) {
+        assert!(weight >= 0.0, "Sample weight must be non-negative");
+        self.grow_labels(target + 1);
+
+        if self.total_samples > 0.0 {
+            self.sample_weight /= self.decay;
+        }
+
+        if self.sample_weight > Self::RESCALE_THRESHOLD {
+            self.rescale();
+        }
 
         for &token in tokens {
-            self.feature_counts[label][token] += 1;
+            self.feature_counts[target][token] += weight * self.sample_weight;
+            self.label_feature_totals[target] += weight * self.sample_weight;
         }
 
-        self.total_samples += 1;
-        self.target_counts[label] += 1;
+        self.total_samples += weight * self.sample_weight;
+        self.target_counts[target] += weight * self.sample_weight;
     }
 
-    /// Predicts the target label for the sparse tokenized text
+    /// Fits the classifier on the specified tokenized text, first growing it to accommodate tokens
+    /// beyond its current number of features (e.g. newly learned by the tokenizer).
+    pub fn partial_fit(&mut self, tokens: &[usize], target: usize) {
+        if let Some(&max_token) = tokens.iter().max() {
+            self.grow_features(max_token + 1);
+        }
+        self.fit(tokens, target);
+    }
+}
+
+impl NaiveBayesClassifier for DecayingNB {
+    /// Fits the classifier on the specified tokenized text, decaying previously fitted samples.
+    fn fit(&mut self, tokens: &[usize], target: usize) {
+        self.fit_weighted(tokens, target, 1.0);
+    }
+
+    /// Predicts the target label for the tokenized text
     fn predict(&self, tokens: &[usize]) -> usize {
-        self.predict_probas(tokens)
-            .iter()
-            .enumerate()
-            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(tgt, _)| tgt)
-            .unwrap()
+        decide(
+            &self.predict_probas(tokens),
+            self.decision_threshold,
+            self.tie_break,
+            |label| self.target_counts[label],
+        )
     }
 
-    /// Returns the target label probabilities for the sparse tokenized text
-    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
+    /// Returns the human-readable names of the target labels, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.class_names.as_deref()
+    }
+
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        let n_features = self.n_features;
+
         self.target_counts
             .iter()
             .enumerate()
             .map(|(tgt, &count)| {
-                let mut prob = 0.0;
+                let prior = (count / self.sample_weight + self.laplace_factor)
+                    / (self.total_samples / self.sample_weight
+                        + self.target_counts.len() as f64 * self.laplace_factor);
+
+                let mut log_prob = prior.ln();
+
                 for &token in tokens {
-                    prob += ((self.feature_counts[tgt][token] as f64 + self.laplace_factor)
-                        / (count as f64 + self.target_counts.len() as f64 * self.laplace_factor))
+                    if token >= n_features {
+                        continue;
+                    }
+
+                    let feature_count = self.feature_counts[tgt][token] / self.sample_weight;
+                    let total_features = self.label_feature_totals[tgt] / self.sample_weight;
+
+                    log_prob += ((feature_count + self.laplace_factor)
+                        / (total_features + n_features as f64 * self.laplace_factor))
                         .ln();
                 }
-                prob += ((count as f64 + self.laplace_factor)
-                    / (self.total_samples as f64 + 2.0 * self.laplace_factor))
-                    .ln();
-                prob
+
+                log_prob
             })
             .collect::<Vec<f64>>()
             .into_boxed_slice()
     }
 }
 
-/// A Naive Bayes classifier using multinomial features (word frequency).
+/// A multinomial Naive Bayes classifier storing approximate feature counts in a count-min sketch per
+/// label, so that the memory used by the model doesn't depend on the vocabulary size.
 #[derive(Serialize, Deserialize)]
-pub struct MultinomialNB {
-    /// Feature counts for each label.
-    feature_counts: Box<[Box<[usize]>]>,
+pub struct SketchNB {
+    /// Count-min sketch of the feature counts for each label, stored as `depth` rows of `width` counters.
+    sketches: Box<[Box<[usize]>]>,
     /// Total feature counts per label.
     label_feature_totals: Box<[usize]>,
     /// Count of target labels.
     target_counts: Box<[usize]>,
-    /// The Laplace smoothing factor.
-    laplace_factor: f64,
     /// Total number of samples.
     total_samples: usize,
+    /// The Laplace smoothing factor.
+    laplace_factor: f64,
+    /// The number of features used for smoothing.
+    n_features: usize,
+    /// Number of counters per sketch row.
+    width: usize,
+    /// Number of sketch rows (hash functions).
+    depth: usize,
+    /// Probability of label 1 above which two-label models predict it, instead of the argmax.
+    decision_threshold: Option<f64>,
+    /// Human-readable names of the target labels, if any.
+    class_names: Option<Box<[Box<str>]>>,
+    /// How ties between labels are broken when predicting.
+    tie_break: TieBreak,
 }
 
-impl MultinomialNB {
-    pub fn new(n_features: usize, n_labels: usize, laplace_smoothing: f64) -> Self {
+impl SketchNB {
+    /// Mersenne prime used by the row hash functions.
+    const HASH_PRIME: u64 = (1 << 61) - 1;
+
+    /// Creates a new classifier using `depth` hash functions each mapping to `width` counters per label.
+    pub fn new(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        width: usize,
+        depth: usize,
+    ) -> Self {
+        assert!(width > 0 && depth > 0, "Sketch dimensions must be non-zero");
+
         Self {
-            feature_counts: vec![vec![0; n_features].into_boxed_slice(); n_labels]
-                .into_boxed_slice(),
+            sketches: vec![vec![0; width * depth].into_boxed_slice(); n_labels].into_boxed_slice(),
             label_feature_totals: vec![0; n_labels].into_boxed_slice(),
-            total_samples: 0,
             target_counts: vec![0; n_labels].into_boxed_slice(),
+            total_samples: 0,
             laplace_factor: laplace_smoothing,
+            n_features,
+            width,
+            depth,
+            decision_threshold: None,
+            class_names: None,
+            tie_break: TieBreak::default(),
         }
     }
-}
 
-impl NaiveBayesClassifier for MultinomialNB {
-    /// Predicts the target label for the tokenized text
-    fn predict(&self, tokens: &[usize]) -> usize {
-        self.predict_probas(tokens)
-            .iter()
-            .enumerate()
-            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(tgt, _)| tgt)
-            .unwrap()
+    /// Creates a new classifier whose sketch dimensions guarantee that counts are overestimated by at
+    /// most `epsilon` times the total feature count, with probability `1 - delta`.
+    pub fn with_error_bounds(
+        n_features: usize,
+        n_labels: usize,
+        laplace_smoothing: f64,
+        epsilon: f64,
+        delta: f64,
+    ) -> Self {
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        Self::new(n_features, n_labels, laplace_smoothing, width, depth)
+    }
+
+    /// Returns the counter index of the token for the specified sketch row.
+    fn slot(&self, row: usize, token: usize) -> usize {
+        // derive the row hash parameters from the row index using splitmix64.
+        let mut seed = (row as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        seed ^= seed >> 31;
+
+        let a = (seed % (Self::HASH_PRIME - 1) + 1) as u128;
+        let b = ((seed >> 32) % Self::HASH_PRIME) as u128;
+        let hash = (a * token as u128 + b) % Self::HASH_PRIME as u128;
+
+        row * self.width + (hash as usize % self.width)
+    }
+
+    /// The number of target labels the classifier is currently sized for.
+    #[inline]
+    pub fn n_labels(&self) -> usize {
+        self.target_counts.len()
+    }
+
+    /// Sets the probability of label 1 above which two-label models predict it, instead of the
+    /// label with the highest probability. Has no effect on models with more labels.
+    pub fn set_decision_threshold(&mut self, decision_threshold: Option<f64>) {
+        validate_decision_threshold(decision_threshold);
+        self.decision_threshold = decision_threshold;
+    }
+
+    /// Sets the human-readable names of the target labels, e.g. from a
+    /// [`crate::labels::LabelEncoder`]. There must be exactly one name per target label.
+    pub fn set_class_names(&mut self, class_names: Box<[Box<str>]>) {
+        assert_eq!(
+            class_names.len(),
+            self.n_labels(),
+            "There must be exactly one class name per target label"
+        );
+        self.class_names = Some(class_names);
+    }
+
+    /// Sets how ties between labels are broken when predicting.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    /// Grows the classifier to accommodate `n_labels` target labels. Never shrinks the classifier.
+    pub fn grow_labels(&mut self, n_labels: usize) {
+        grow_feature_rows(&mut self.sketches, n_labels, self.width * self.depth);
+        grow_slice(&mut self.label_feature_totals, n_labels);
+        grow_slice(&mut self.target_counts, n_labels);
+    }
+
+    /// Returns the estimated count of the token for the specified label.
+    pub fn feature_count(&self, label: usize, token: usize) -> usize {
+        (0..self.depth)
+            .map(|row| self.sketches[label][self.slot(row, token)])
+            .min()
+            .unwrap_or(0)
     }
+}
 
+impl NaiveBayesClassifier for SketchNB {
     /// Fits the classifier on the specified tokenized text.
     fn fit(&mut self, tokens: &[usize], target: usize) {
-        assert!(target < self.target_counts.len());
-        tokens
-            .iter()
-            .copied()
-            .fold(HashMap::new(), |mut map, val| {
-                map.entry(val).and_modify(|frq| *frq += 1).or_insert(1usize);
-                map
-            })
-            .into_iter()
-            .for_each(|(token, count)| {
-                self.feature_counts[target][token] += count;
-                self.label_feature_totals[target] += count;
-            });
+        self.grow_labels(target + 1);
+
+        for &token in tokens {
+            for row in 0..self.depth {
+                let slot = self.slot(row, token);
+                self.sketches[target][slot] += 1;
+            }
+            self.label_feature_totals[target] += 1;
+        }
 
         self.total_samples += 1;
         self.target_counts[target] += 1;
     }
 
-    /// Returns the target label probabilities for the tokenized text
-    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
-        let token_map = tokens.iter().copied().fold(HashMap::new(), |mut map, val| {
-            map.entry(val).and_modify(|frq| *frq += 1).or_insert(1usize);
-            map
-        });
-        let n_features = self.feature_counts[0].len();
+    /// Predicts the target label for the tokenized text
+    fn predict(&self, tokens: &[usize]) -> usize {
+        decide(
+            &self.predict_probas(tokens),
+            self.decision_threshold,
+            self.tie_break,
+            |label| self.target_counts[label] as f64,
+        )
+    }
+
+    /// Returns the human-readable names of the target labels, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.class_names.as_deref()
+    }
 
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
         self.target_counts
             .iter()
             .enumerate()
@@ -170,23 +2189,171 @@ impl NaiveBayesClassifier for MultinomialNB {
 
                 let mut log_prob = prior.ln();
 
-                for (token, &token_count) in &token_map {
-                    if *token >= n_features {
-                        continue;
-                    }
-
-                    let feature_count = self.feature_counts[tgt][*token] as f64;
+                for &token in tokens {
+                    let feature_count = self.feature_count(tgt, token) as f64;
                     let total_features = self.label_feature_totals[tgt] as f64;
 
-                    let token_prob = (feature_count + self.laplace_factor)
-                        / (total_features + n_features as f64 * self.laplace_factor);
-
-                    log_prob += token_count as f64 * token_prob.ln();
+                    log_prob += ((feature_count + self.laplace_factor)
+                        / (total_features + self.n_features as f64 * self.laplace_factor))
+                        .ln();
                 }
 
-                log_prob.exp()
+                log_prob
             })
             .collect::<Vec<f64>>()
             .into_boxed_slice()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that the probabilities are finite, non-zero and sum to 1.
+    fn assert_normalized(probas: &[f64]) {
+        assert!(
+            probas.iter().all(|p| p.is_finite() && *p > 0.0),
+            "Probabilities must be finite and non-zero: {:?}",
+            probas
+        );
+        assert!((probas.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bernoulli_probas_are_normalized_on_long_documents() {
+        let n_features = 5000;
+        let mut nb = BernouliNB::new(n_features, 2, 1.0);
+        nb.fit(&(0..n_features / 2).collect::<Vec<_>>(), 0);
+        nb.fit(&(n_features / 2..n_features).collect::<Vec<_>>(), 1);
+
+        // Several thousand present tokens, whose joint likelihood underflows without normalizing
+        // in log space.
+        let document: Vec<usize> = (0..n_features).collect();
+        assert!(nb
+            .joint_log_likelihood(&document)
+            .iter()
+            .all(|&ll| ll < -745.0));
+        assert_normalized(&nb.predict_probas(&document));
+    }
+
+    #[test]
+    fn multinomial_probas_are_normalized_on_long_documents() {
+        let mut nb = MultinomialNB::new(10, 2, 1.0);
+        nb.fit(&[0, 1, 2, 3, 4, 5], 0);
+        nb.fit(&[4, 5, 6, 7, 8, 9], 1);
+
+        let document: Vec<usize> = (0..5000).map(|i| i % 10).collect();
+        assert!(nb
+            .joint_log_likelihood(&document)
+            .iter()
+            .all(|&ll| ll < -745.0));
+        assert_normalized(&nb.predict_probas(&document));
+    }
+
+    #[test]
+    #[should_panic(expected = "exactly one custom prior per target label")]
+    fn custom_priors_must_cover_every_label() {
+        let mut nb = MultinomialNB::new(4, 3, 1.0);
+        nb.set_class_prior(ClassPrior::Custom(Box::new([0.5, 0.5])));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't grow past the labels covered by the custom priors")]
+    fn labels_cannot_grow_past_custom_priors() {
+        let mut nb = BernouliNB::new(4, 2, 1.0);
+        nb.set_class_prior(ClassPrior::Custom(Box::new([0.3, 0.7])));
+        nb.fit(&[0, 1], 1);
+        nb.fit(&[2, 3], 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't unfit more than the fitted occurrences of a token")]
+    fn bernoulli_unfit_rejects_unseen_tokens() {
+        let mut nb = BernouliNB::new(4, 2, 1.0);
+        nb.fit(&[0, 1], 0);
+        nb.unfit(&[0, 2], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't unfit more than the fitted occurrences of a token")]
+    fn multinomial_unfit_rejects_unseen_tokens() {
+        let mut nb = MultinomialNB::new(4, 2, 1.0);
+        nb.fit(&[0, 1], 0);
+        nb.fit(&[2, 3], 1);
+        nb.unfit(&[2], 0);
+    }
+
+    #[test]
+    fn unfit_rejection_leaves_counts_untouched() {
+        let mut nb = MultinomialNB::new(4, 2, 1.0);
+        nb.fit(&[0, 1], 0);
+        nb.fit(&[2, 3], 1);
+        let probas = nb.predict_probas(&[0, 2]);
+
+        let unfit = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| nb.unfit(&[0, 2], 0)));
+        assert!(unfit.is_err());
+        assert_eq!(nb.predict_probas(&[0, 2]), probas);
+    }
+
+    #[test]
+    fn integer_counts_reject_fractional_weights_before_fitting() {
+        let mut nb = BernouliNB::with_storage(4, 2, 1.0, CountStorage::DenseU32);
+        nb.fit(&[0, 1], 0);
+        let probas = nb.predict_probas(&[0, 2]);
+
+        let fit = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            nb.fit_weighted(&[0, 2], 0, 0.5)
+        }));
+        assert!(fit.is_err());
+        assert_eq!(nb.predict_probas(&[0, 2]), probas);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sublinear term frequencies aren't supported by 32-bit integer")]
+    fn integer_counts_reject_sublinear_tf() {
+        let mut nb = MultinomialNB::with_storage(4, 2, 1.0, CountStorage::DenseU32);
+        nb.set_sublinear_tf(true);
+    }
+
+    #[test]
+    fn integer_counts_reject_float_features_before_fitting() {
+        let mut nb = MultinomialNB::with_storage(4, 2, 1.0, CountStorage::DenseU32);
+        nb.fit_features(&[(0, 2.0), (1, 1.0)], 0);
+        let probas = nb.predict_probas(&[0, 2]);
+
+        let fit = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            nb.fit_features(&[(2, 1.0), (0, 0.5)], 0)
+        }));
+        assert!(fit.is_err());
+        assert_eq!(nb.predict_probas(&[0, 2]), probas);
+    }
+
+    #[test]
+    #[should_panic(expected = "Jelinek-Mercer interpolation weight must be in the ]0, 1] range")]
+    fn jelinek_mercer_rejects_zero_interpolation_weight() {
+        let mut nb = MultinomialNB::new(4, 2, 1.0);
+        nb.set_smoothing(Smoothing::JelinekMercer(0.0));
+    }
+
+    #[test]
+    fn unk_token_is_scored_as_unseen() {
+        let mut multinomial = MultinomialNB::new(3, 2, 1.0);
+        multinomial.fit(&[0, 0, 1], 0);
+        multinomial.fit(&[2], 1);
+        let mut bernouli = BernouliNB::new(3, 2, 1.0);
+        bernouli.fit(&[0, 1], 0);
+        bernouli.fit(&[2], 1);
+
+        let unknown = [0, 0];
+        let multinomial_probas = multinomial.predict_probas(&unknown);
+        let bernouli_probas = bernouli.predict_probas(&unknown);
+        assert_eq!(multinomial.predict(&unknown), 0);
+
+        multinomial.set_unk_token(Some(0));
+        bernouli.set_unk_token(Some(0));
+        assert_ne!(multinomial.predict_probas(&unknown), multinomial_probas);
+        assert_ne!(bernouli.predict_probas(&unknown), bernouli_probas);
+        assert_eq!(multinomial.predict(&unknown), 1);
+        assert_normalized(&bernouli.predict_probas(&unknown));
+    }
+}
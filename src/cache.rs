@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+/// Index marking the absence of a neighbouring entry.
+const NIL: usize = usize::MAX;
+
+/// A cached value along with its neighbours in the recency list.
+#[derive(Debug)]
+struct Entry<V> {
+    key: Box<str>,
+    value: V,
+    /// Index of the more recently used entry.
+    prev: usize,
+    /// Index of the less recently used entry.
+    next: usize,
+}
+
+/// A fixed capacity cache keyed by strings, evicting the least recently used entry when full.
+#[derive(Debug)]
+pub(crate) struct LruCache<V> {
+    capacity: usize,
+    /// Index of the entry of each key.
+    index: HashMap<Box<str>, usize>,
+    entries: Vec<Entry<V>>,
+    /// Index of the most recently used entry.
+    head: usize,
+    /// Index of the least recently used entry.
+    tail: usize,
+}
+
+impl<V> LruCache<V> {
+    /// Creates an empty cache holding at most `capacity` entries.
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Cache capacity must be non-zero");
+        Self {
+            capacity,
+            index: HashMap::new(),
+            entries: Vec::new(),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    /// Returns the value of the key, marking it as the most recently used.
+    pub(crate) fn get(&mut self, key: &str) -> Option<&V> {
+        let idx = *self.index.get(key)?;
+        self.detach(idx);
+        self.push_front(idx);
+        Some(&self.entries[idx].value)
+    }
+
+    /// Inserts the value of the key as the most recently used, evicting the least recently used
+    /// entry if the cache is full.
+    pub(crate) fn insert(&mut self, key: &str, value: V) {
+        if let Some(&idx) = self.index.get(key) {
+            self.entries[idx].value = value;
+            self.detach(idx);
+            self.push_front(idx);
+            return;
+        }
+
+        let idx = if self.entries.len() < self.capacity {
+            self.entries.push(Entry {
+                key: key.into(),
+                value,
+                prev: NIL,
+                next: NIL,
+            });
+            self.entries.len() - 1
+        } else {
+            let idx = self.tail;
+            self.detach(idx);
+            let entry = &mut self.entries[idx];
+            self.index.remove(&entry.key);
+            entry.key = key.into();
+            entry.value = value;
+            idx
+        };
+
+        self.index.insert(key.into(), idx);
+        self.push_front(idx);
+    }
+
+    /// Removes every entry.
+    pub(crate) fn clear(&mut self) {
+        self.index.clear();
+        self.entries.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// Unlinks the entry from the recency list.
+    fn detach(&mut self, idx: usize) {
+        let (prev, next) = (self.entries[idx].prev, self.entries[idx].next);
+        match prev {
+            NIL => self.head = next,
+            prev => self.entries[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.entries[next].prev = prev,
+        }
+    }
+
+    /// Links the entry at the front of the recency list.
+    fn push_front(&mut self, idx: usize) {
+        self.entries[idx].prev = NIL;
+        self.entries[idx].next = self.head;
+        match self.head {
+            NIL => self.tail = idx,
+            head => self.entries[head].prev = idx,
+        }
+        self.head = idx;
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{de::DeserializeOwned, de::Error, Deserialize, Serialize};
+use std::io::Read;
+
+use crate::bayes::{decide, log_sum_exp, NaiveBayesClassifier, TieBreak};
+
+/// The method used to map the classifier scores to calibrated probabilities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationMethod {
+    /// Fits a sigmoid on the log-odds of each label (Platt scaling).
+    Sigmoid,
+    /// Fits a non-decreasing piecewise-linear function on the log-odds of each label.
+    /// Needs more held-out samples than sigmoid calibration but makes no assumption on the shape of the mapping.
+    Isotonic,
+}
+
+/// The fitted one-vs-rest mapping of a single label, from its log-odds to its probability.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum LabelCalibration {
+    /// `p = 1 / (1 + exp(a * log_odds + b))`
+    Sigmoid { a: f64, b: f64 },
+    /// Probabilities at sorted log-odds breakpoints, linearly interpolated in-between.
+    Isotonic {
+        log_odds: Box<[f64]>,
+        probas: Box<[f64]>,
+    },
+}
+
+impl LabelCalibration {
+    /// Fits Platt's sigmoid on the (log-odds, is label) pairs by Newton's method with backtracking,
+    /// using the regularized targets from Platt's paper.
+    fn fit_sigmoid(samples: &[(f64, bool)]) -> Self {
+        let positives = samples.iter().filter(|(_, positive)| *positive).count() as f64;
+        let negatives = samples.len() as f64 - positives;
+        let hi_target = (positives + 1.0) / (positives + 2.0);
+        let lo_target = 1.0 / (negatives + 2.0);
+        let targets: Vec<f64> = samples
+            .iter()
+            .map(|(_, positive)| if *positive { hi_target } else { lo_target })
+            .collect();
+
+        let objective = |a: f64, b: f64| -> f64 {
+            samples
+                .iter()
+                .zip(targets.iter())
+                .map(|((score, _), t)| {
+                    let f = score * a + b;
+                    if f >= 0.0 {
+                        t * f + (-f).exp().ln_1p()
+                    } else {
+                        (t - 1.0) * f + f.exp().ln_1p()
+                    }
+                })
+                .sum()
+        };
+
+        let (mut a, mut b) = (0.0, ((negatives + 1.0) / (positives + 1.0)).ln());
+        let mut value = objective(a, b);
+
+        for _ in 0..100 {
+            let (mut h11, mut h22, mut h21, mut g1, mut g2) = (1e-12, 1e-12, 0.0, 0.0, 0.0);
+            for ((score, _), t) in samples.iter().zip(targets.iter()) {
+                let f = score * a + b;
+                let (p, q) = if f >= 0.0 {
+                    let e = (-f).exp();
+                    (e / (1.0 + e), 1.0 / (1.0 + e))
+                } else {
+                    let e = f.exp();
+                    (1.0 / (1.0 + e), e / (1.0 + e))
+                };
+                let d2 = p * q;
+                h11 += score * score * d2;
+                h22 += d2;
+                h21 += score * d2;
+                let d1 = t - p;
+                g1 += score * d1;
+                g2 += d1;
+            }
+
+            if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+                break;
+            }
+
+            let det = h11 * h22 - h21 * h21;
+            let da = -(h22 * g1 - h21 * g2) / det;
+            let db = -(-h21 * g1 + h11 * g2) / det;
+            let gd = g1 * da + g2 * db;
+
+            let mut step = 1.0;
+            while step >= 1e-10 {
+                let new_value = objective(a + step * da, b + step * db);
+                if new_value < value + 1e-4 * step * gd {
+                    a += step * da;
+                    b += step * db;
+                    value = new_value;
+                    break;
+                }
+                step /= 2.0;
+            }
+
+            if step < 1e-10 {
+                break;
+            }
+        }
+
+        LabelCalibration::Sigmoid { a, b }
+    }
+
+    /// Fits a non-decreasing mapping on the (log-odds, is label) pairs using the pool adjacent
+    /// violators algorithm.
+    fn fit_isotonic(samples: &[(f64, bool)]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Blocks of (mean log-odds, mean target, weight), merged while they violate monotonicity or
+        // share the same log-odds.
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::with_capacity(sorted.len());
+        for (score, positive) in sorted {
+            let mut block = (score, if positive { 1.0 } else { 0.0 }, 1.0);
+            while let Some(&(prev_score, prev_proba, prev_weight)) = blocks.last() {
+                if prev_proba < block.1 && prev_score < block.0 {
+                    break;
+                }
+                let weight = prev_weight + block.2;
+                block = (
+                    (prev_score * prev_weight + block.0 * block.2) / weight,
+                    (prev_proba * prev_weight + block.1 * block.2) / weight,
+                    weight,
+                );
+                blocks.pop();
+            }
+            blocks.push(block);
+        }
+
+        LabelCalibration::Isotonic {
+            log_odds: blocks.iter().map(|block| block.0).collect(),
+            probas: blocks.iter().map(|block| block.1).collect(),
+        }
+    }
+
+    /// Maps the log-odds of the label to its calibrated probability.
+    fn calibrate(&self, log_odds: f64) -> f64 {
+        match self {
+            LabelCalibration::Sigmoid { a, b } => 1.0 / (1.0 + (a * log_odds + b).exp()),
+            LabelCalibration::Isotonic {
+                log_odds: xs,
+                probas,
+            } => {
+                let idx = xs.partition_point(|&x| x < log_odds);
+                if idx == 0 {
+                    probas[0]
+                } else if idx == xs.len() {
+                    probas[xs.len() - 1]
+                } else {
+                    let (x0, x1) = (xs[idx - 1], xs[idx]);
+                    let (p0, p1) = (probas[idx - 1], probas[idx]);
+                    p0 + (p1 - p0) * (log_odds - x0) / (x1 - x0)
+                }
+            }
+        }
+    }
+}
+
+/// A wrapper which calibrates the over-confident probabilities of the wrapped classifier using a
+/// mapping fitted on held-out samples.
+///
+/// Each label is calibrated one-vs-rest on its log-odds, then the probabilities are renormalized.
+/// Until [`CalibratedClassifier::calibrate`] is called, the probabilities of the wrapped classifier
+/// are returned unchanged.
+#[derive(Serialize, Deserialize)]
+pub struct CalibratedClassifier<C: NaiveBayesClassifier> {
+    /// The wrapped base classifier.
+    classifier: C,
+    /// The per-label calibration mappings, if fitted.
+    calibrations: Option<Box<[LabelCalibration]>>,
+}
+
+impl<C: NaiveBayesClassifier> CalibratedClassifier<C> {
+    pub fn new(classifier: C) -> Self {
+        Self {
+            classifier,
+            calibrations: None,
+        }
+    }
+
+    /// Fits the calibration mapping on held-out samples, which shouldn't have been used to fit the
+    /// wrapped classifier.
+    pub fn calibrate(&mut self, held_out: &[(Vec<usize>, usize)], method: CalibrationMethod) {
+        assert!(
+            !held_out.is_empty(),
+            "Calibration requires at least one held-out sample"
+        );
+
+        let log_odds: Vec<Box<[f64]>> = held_out
+            .iter()
+            .map(|(tokens, _)| self.log_odds(tokens))
+            .collect();
+        let n_labels = log_odds[0].len();
+
+        let calibrations = (0..n_labels)
+            .map(|label| {
+                let samples: Vec<(f64, bool)> = log_odds
+                    .iter()
+                    .zip(held_out.iter())
+                    .map(|(odds, (_, target))| (odds[label], *target == label))
+                    .collect();
+
+                match method {
+                    CalibrationMethod::Sigmoid => LabelCalibration::fit_sigmoid(&samples),
+                    CalibrationMethod::Isotonic => LabelCalibration::fit_isotonic(&samples),
+                }
+            })
+            .collect();
+
+        self.calibrations = Some(calibrations);
+    }
+
+    /// Returns whether a calibration mapping has been fitted.
+    #[inline]
+    pub fn is_calibrated(&self) -> bool {
+        self.calibrations.is_some()
+    }
+
+    /// Returns the one-vs-rest log-odds of each label for the tokenized text.
+    fn log_odds(&self, tokens: &[usize]) -> Box<[f64]> {
+        let jll = self.classifier.joint_log_likelihood(tokens);
+
+        (0..jll.len())
+            .map(|label| {
+                let rest: Vec<f64> = jll
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != label)
+                    .map(|(_, &x)| x)
+                    .collect();
+                jll[label] - log_sum_exp(&rest)
+            })
+            .collect()
+    }
+
+    /// Returns the wrapped classifier.
+    pub fn classifier(&self) -> &C {
+        &self.classifier
+    }
+
+    /// Consumes the wrapper and returns the wrapped classifier.
+    pub fn into_inner(self) -> C {
+        self.classifier
+    }
+}
+
+impl<C: NaiveBayesClassifier + DeserializeOwned> CalibratedClassifier<C> {
+    /// Loads a calibrated classifier from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+}
+
+impl<C: NaiveBayesClassifier + Serialize> CalibratedClassifier<C> {
+    /// Saves a calibrated classifier to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+}
+
+impl<C: NaiveBayesClassifier> NaiveBayesClassifier for CalibratedClassifier<C> {
+    /// Fits the wrapped classifier on the specified tokenized text.
+    fn fit(&mut self, tokens: &[usize], label: usize) {
+        self.classifier.fit(tokens, label)
+    }
+
+    /// Predicts the target label with the highest calibrated probability for the tokenized text,
+    /// the lowest one among tied labels
+    fn predict(&self, tokens: &[usize]) -> usize {
+        decide(
+            &self.predict_probas(tokens),
+            None,
+            TieBreak::LowestIndex,
+            |_| 0.0,
+        )
+    }
+
+    /// Returns the calibrated target label probabilities for the tokenized text
+    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
+        let Some(calibrations) = self.calibrations.as_ref() else {
+            return self.classifier.predict_probas(tokens);
+        };
+
+        let mut probas: Box<[f64]> = self
+            .log_odds(tokens)
+            .iter()
+            .zip(calibrations.iter())
+            .map(|(&log_odds, calibration)| calibration.calibrate(log_odds))
+            .collect();
+
+        let total: f64 = probas.iter().sum();
+        if total > 0.0 {
+            probas.iter_mut().for_each(|p| *p /= total);
+        } else {
+            probas.fill(1.0 / probas.len() as f64);
+        }
+        probas
+    }
+
+    /// Returns the uncalibrated target label joint log-likelihoods of the wrapped classifier
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.classifier.joint_log_likelihood(tokens)
+    }
+
+    /// Returns the human-readable names of the target labels of the wrapped classifier, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.classifier.class_names()
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{de::Error, Deserialize, Serialize};
+use std::io::Read;
+
+use crate::bayes::{decide, scale_frequency, LogProbTables, TieBreak};
+
+/// How the tokens of a text are turned into the feature values scored by a compact classifier,
+/// matching the classifier it was converted from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) enum TokenScoring {
+    /// Token frequencies, capped and dampened as by [`crate::bayes::MultinomialNB`].
+    Frequencies {
+        sublinear_tf: bool,
+        max_tf: Option<usize>,
+    },
+    /// Presence of the tokens occurring at least the binarization threshold times, as by
+    /// [`crate::bayes::BernouliNB`].
+    Presence { binarize_threshold: usize },
+}
+
+impl TokenScoring {
+    /// Returns the (token, value) features of the tokenized text.
+    fn features(&self, tokens: &[usize]) -> Vec<(usize, f32)> {
+        let mut sorted = tokens.to_vec();
+        sorted.sort_unstable();
+
+        let occurrences = sorted.chunk_by(|a, b| a == b);
+        match *self {
+            TokenScoring::Frequencies {
+                sublinear_tf,
+                max_tf,
+            } => occurrences
+                .map(|occurrences| {
+                    let frequency = scale_frequency(occurrences.len() as f64, max_tf, sublinear_tf);
+                    (occurrences[0], frequency as f32)
+                })
+                .collect(),
+            TokenScoring::Presence { binarize_threshold } => occurrences
+                .filter(|occurrences| occurrences.len() >= binarize_threshold)
+                .map(|occurrences| (occurrences[0], 1.0))
+                .collect(),
+        }
+    }
+}
+
+/// A compact inference-only Naive Bayes classifier using precomputed f32 log-probabilities, for
+/// faster and smaller inference where f64 precision isn't needed.
+///
+/// Obtained by converting a trained classifier, e.g. using [`crate::bayes::MultinomialNB::to_compact`].
+#[derive(Serialize, Deserialize)]
+pub struct CompactNB {
+    /// Log prior probability of each label.
+    class_log_prior: Box<[f32]>,
+    /// Log probability of each feature given each label, indexed as `label * n_features + token`.
+    feature_log_prob: Box<[f32]>,
+    /// Number of features.
+    n_features: usize,
+    /// Probability of label 1 above which two-label models predict it, instead of the argmax.
+    decision_threshold: Option<f64>,
+    /// How tied labels are resolved, ranking them by their prior if needed.
+    tie_break: TieBreak,
+    /// How the tokens of a text are turned into feature values.
+    token_scoring: TokenScoring,
+}
+
+impl CompactNB {
+    pub(crate) fn from_tables(
+        tables: &LogProbTables,
+        decision_threshold: Option<f64>,
+        tie_break: TieBreak,
+        token_scoring: TokenScoring,
+    ) -> Self {
+        Self {
+            class_log_prior: tables.class_log_prior.iter().map(|&x| x as f32).collect(),
+            feature_log_prob: tables.feature_log_prob.iter().map(|&x| x as f32).collect(),
+            n_features: tables.n_features,
+            decision_threshold,
+            tie_break,
+            token_scoring,
+        }
+    }
+
+    /// Loads a classifier from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves a classifier to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// Predicts the target label for the tokenized text, using the decision threshold and
+    /// tie-break policy of the classifier it was converted from.
+    pub fn predict(&self, tokens: &[usize]) -> usize {
+        let probas: Vec<f64> = self
+            .predict_probas(tokens)
+            .iter()
+            .map(|&p| p as f64)
+            .collect();
+        decide(&probas, self.decision_threshold, self.tie_break, |label| {
+            self.class_log_prior[label] as f64
+        })
+    }
+
+    /// Returns the target label probabilities for the tokenized text, normalized to sum to 1.
+    pub fn predict_probas(&self, tokens: &[usize]) -> Box<[f32]> {
+        let log_likelihoods = self.joint_log_likelihood(tokens);
+        let max = log_likelihoods
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let log_evidence = max
+            + log_likelihoods
+                .iter()
+                .map(|log_prob| (log_prob - max).exp())
+                .sum::<f32>()
+                .ln();
+
+        log_likelihoods
+            .iter()
+            .map(|log_prob| (log_prob - log_evidence).exp())
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    pub fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f32]> {
+        let features = self.token_scoring.features(tokens);
+        self.class_log_prior
+            .iter()
+            .enumerate()
+            .map(|(tgt, log_prior)| {
+                let log_probs = &self.feature_log_prob[tgt * self.n_features..][..self.n_features];
+                log_prior
+                    + features
+                        .iter()
+                        .filter_map(|&(token, value)| log_probs.get(token).map(|lp| value * lp))
+                        .sum::<f32>()
+            })
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+}
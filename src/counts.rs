@@ -0,0 +1,256 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::remap::FeatureRemap;
+
+/// How the per-label feature counts of a classifier are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CountStorage {
+    /// A dense array of counts for every label and feature. Fastest, but its size grows with the
+    /// vocabulary size for every label.
+    #[default]
+    Dense,
+    /// A dense array of 32-bit integer counts, using half the memory of [`CountStorage::Dense`].
+    /// Only integer sample weights and feature values are supported, fractional ones being rejected
+    /// with a panic before any count changes. Overflowing a count panics.
+    DenseU32,
+    /// A hash map per label storing only the non-zero counts. Suited to huge vocabularies where most
+    /// tokens never appear for most labels.
+    Sparse,
+}
+
+/// A numeric type feature counts can be stored as.
+pub(crate) trait Count: Copy + Default + PartialEq + Serialize + DeserializeOwned {
+    fn to_f64(self) -> f64;
+
+    /// Adds the value to the count, panicking if the result can't be represented.
+    fn add_f64(&mut self, value: f64);
+}
+
+impl Count for f64 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    #[inline]
+    fn add_f64(&mut self, value: f64) {
+        *self += value;
+    }
+}
+
+impl Count for u32 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn add_f64(&mut self, value: f64) {
+        assert!(
+            value.fract() == 0.0,
+            "Integer feature counts only support integer weights"
+        );
+        let result = *self as f64 + value;
+        assert!(
+            (0.0..=u32::MAX as f64).contains(&result),
+            "Feature count overflowed its 32-bit storage"
+        );
+        *self = result as u32;
+    }
+}
+
+/// Contiguous per-label feature counts indexed as `label * n_features + token`.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct DenseCounts<T> {
+    counts: Box<[T]>,
+    n_features: usize,
+    /// Number of non-zero counts per label.
+    distinct: Box<[usize]>,
+}
+
+impl<T: Count> DenseCounts<T> {
+    fn new(n_labels: usize, n_features: usize) -> Self {
+        Self {
+            counts: vec![T::default(); n_labels * n_features].into_boxed_slice(),
+            n_features,
+            distinct: vec![0; n_labels].into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, label: usize, token: usize) -> f64 {
+        debug_assert!(token < self.n_features);
+        self.counts[label * self.n_features + token].to_f64()
+    }
+
+    #[inline]
+    fn add(&mut self, label: usize, token: usize, value: f64) {
+        assert!(token < self.n_features, "Token is out of the feature range");
+        let count = &mut self.counts[label * self.n_features + token];
+        let was_zero = *count == T::default();
+        count.add_f64(value);
+
+        match (was_zero, *count == T::default()) {
+            (true, false) => self.distinct[label] += 1,
+            (false, true) => self.distinct[label] -= 1,
+            _ => {}
+        }
+    }
+
+    fn grow(&mut self, n_labels: usize, n_features: usize) {
+        let n_features = n_features.max(self.n_features);
+        grow_slice(&mut self.distinct, n_labels);
+
+        if n_features == self.n_features {
+            grow_slice(&mut self.counts, n_labels * n_features);
+        } else {
+            let old_n_labels = self.counts.len().checked_div(self.n_features).unwrap_or(0);
+            let mut grown = vec![T::default(); n_labels.max(old_n_labels) * n_features];
+            for label in 0..old_n_labels {
+                let old_row = label * self.n_features..(label + 1) * self.n_features;
+                grown[label * n_features..][..self.n_features]
+                    .copy_from_slice(&self.counts[old_row]);
+            }
+            self.counts = grown.into_boxed_slice();
+            self.n_features = n_features;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count != T::default())
+            .map(|(idx, &count)| (idx / self.n_features, idx % self.n_features, count.to_f64()))
+    }
+}
+
+/// The per-label feature counts of a classifier.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum FeatureCounts {
+    Dense(DenseCounts<f64>),
+    DenseU32(DenseCounts<u32>),
+    Sparse(Box<[HashMap<usize, f64>]>),
+}
+
+impl FeatureCounts {
+    pub fn new(storage: CountStorage, n_labels: usize, n_features: usize) -> Self {
+        match storage {
+            CountStorage::Dense => FeatureCounts::Dense(DenseCounts::new(n_labels, n_features)),
+            CountStorage::DenseU32 => {
+                FeatureCounts::DenseU32(DenseCounts::new(n_labels, n_features))
+            }
+            CountStorage::Sparse => {
+                FeatureCounts::Sparse(vec![HashMap::new(); n_labels].into_boxed_slice())
+            }
+        }
+    }
+
+    /// Returns how the counts are stored.
+    pub fn storage(&self) -> CountStorage {
+        match self {
+            FeatureCounts::Dense(_) => CountStorage::Dense,
+            FeatureCounts::DenseU32(_) => CountStorage::DenseU32,
+            FeatureCounts::Sparse(_) => CountStorage::Sparse,
+        }
+    }
+
+    /// Returns the counts of the kept features, reindexed according to the remapping.
+    pub fn remap(&self, n_labels: usize, remap: &FeatureRemap) -> Self {
+        let mut remapped = FeatureCounts::new(self.storage(), n_labels, remap.n_features());
+        self.iter()
+            .filter_map(|(label, token, count)| remap.get(token).map(|new| (label, new, count)))
+            .for_each(|(label, token, count)| remapped.add(label, token, count));
+        remapped
+    }
+
+    /// Panics if the value can't be added to the counts, 32-bit integer counts only supporting
+    /// integer values.
+    pub fn validate_value(&self, value: f64) {
+        if let FeatureCounts::DenseU32(_) = self {
+            assert!(
+                value.fract() == 0.0,
+                "32-bit integer feature counts only support integer weights and feature values"
+            );
+        }
+    }
+
+    /// Returns the count of the token for the specified label.
+    #[inline]
+    pub fn get(&self, label: usize, token: usize) -> f64 {
+        match self {
+            FeatureCounts::Dense(counts) => counts.get(label, token),
+            FeatureCounts::DenseU32(counts) => counts.get(label, token),
+            FeatureCounts::Sparse(counts) => counts[label].get(&token).copied().unwrap_or(0.0),
+        }
+    }
+
+    /// Adds the value to the count of the token for the specified label.
+    #[inline]
+    pub fn add(&mut self, label: usize, token: usize, value: f64) {
+        match self {
+            FeatureCounts::Dense(counts) => counts.add(label, token, value),
+            FeatureCounts::DenseU32(counts) => counts.add(label, token, value),
+            FeatureCounts::Sparse(counts) => {
+                let count = counts[label].entry(token).or_insert(0.0);
+                *count += value;
+                if *count == 0.0 {
+                    counts[label].remove(&token);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of tokens with a non-zero count for the specified label.
+    #[inline]
+    pub fn distinct(&self, label: usize) -> usize {
+        match self {
+            FeatureCounts::Dense(counts) => counts.distinct[label],
+            FeatureCounts::DenseU32(counts) => counts.distinct[label],
+            FeatureCounts::Sparse(counts) => counts[label].len(),
+        }
+    }
+
+    /// Grows the counts to `n_labels` labels of `n_features` features. Never shrinks the counts.
+    pub fn grow(&mut self, n_labels: usize, n_features: usize) {
+        match self {
+            FeatureCounts::Dense(counts) => counts.grow(n_labels, n_features),
+            FeatureCounts::DenseU32(counts) => counts.grow(n_labels, n_features),
+            FeatureCounts::Sparse(counts) => grow_slice(counts, n_labels),
+        }
+    }
+
+    /// Returns an iterator over the (label, token, count) triplets of non-zero counts.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (usize, usize, f64)> + '_> {
+        match self {
+            FeatureCounts::Dense(counts) => Box::new(counts.iter()),
+            FeatureCounts::DenseU32(counts) => Box::new(counts.iter()),
+            FeatureCounts::Sparse(counts) => {
+                Box::new(counts.iter().enumerate().flat_map(|(label, row)| {
+                    row.iter()
+                        .map(move |(&token, &count)| (label, token, count))
+                }))
+            }
+        }
+    }
+}
+
+/// Grows the boxed slice to `len` default-initialized elements. Never shrinks the slice.
+pub(crate) fn grow_slice<T: Clone + Default>(slice: &mut Box<[T]>, len: usize) {
+    if slice.len() < len {
+        let mut grown = std::mem::take(slice).into_vec();
+        grown.resize(len, T::default());
+        *slice = grown.into_boxed_slice();
+    }
+}
+
+/// Grows the per-label rows of feature counts to `n_labels` rows of `n_features` features.
+pub(crate) fn grow_feature_rows<T: Clone + Default>(
+    rows: &mut Box<[Box<[T]>]>,
+    n_labels: usize,
+    n_features: usize,
+) {
+    grow_slice(rows, n_labels);
+    rows.iter_mut().for_each(|row| grow_slice(row, n_features));
+}
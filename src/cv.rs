@@ -0,0 +1,150 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    bayes::{DecrementalClassifier, NaiveBayesClassifier},
+    error::RnbError,
+    metrics::ConfusionMatrix,
+    tokenizer::{Tokenizer, TokenizerBuilder},
+};
+
+/// Splits the shuffled indices of the samples into `k` folds of nearly equal size, and returns the
+/// training and validation indices of each fold, each sample being validated exactly once.
+pub fn kfold(n_samples: usize, k: usize, seed: u64) -> Vec<(Vec<usize>, Vec<usize>)> {
+    assert!(
+        k >= 2 && k <= n_samples,
+        "Number of folds must be at least 2 and at most the number of samples"
+    );
+
+    let mut indices: Vec<usize> = (0..n_samples).collect();
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    (0..k)
+        .map(|fold| {
+            let (start, end) = (fold * n_samples / k, (fold + 1) * n_samples / k);
+            let training = indices[..start]
+                .iter()
+                .chain(indices[end..].iter())
+                .copied()
+                .collect();
+            (training, indices[start..end].to_vec())
+        })
+        .collect()
+}
+
+/// The validation confusion matrices of the folds of a cross-validation.
+#[derive(Debug, Clone)]
+pub struct CrossValidation {
+    folds: Box<[ConfusionMatrix]>,
+}
+
+impl CrossValidation {
+    /// Returns the validation confusion matrix of each fold.
+    pub fn folds(&self) -> &[ConfusionMatrix] {
+        &self.folds
+    }
+
+    /// Returns the confusion matrix of every validated sample, summing the matrices of the folds.
+    pub fn matrix(&self) -> ConfusionMatrix {
+        let mut matrix = ConfusionMatrix::new(self.folds[0].n_classes());
+        self.folds.iter().for_each(|fold| matrix.merge(fold));
+        matrix
+    }
+
+    /// Returns the mean and standard deviation over the folds of a metric of their confusion
+    /// matrix, e.g. `|matrix| matrix.f1(1)`.
+    pub fn mean_std(&self, metric: impl Fn(&ConfusionMatrix) -> f64) -> (f64, f64) {
+        let values: Vec<f64> = self.folds.iter().map(metric).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        (mean, variance.sqrt())
+    }
+}
+
+/// Cross-validates a classifier on the labeled texts with `k` folds shuffled with the seed. For
+/// every fold, a tokenizer is built from the configuration and fitted on the training texts, then
+/// a classifier built by `factory` given the vocabulary size and number of labels is trained on them
+/// and evaluated on the validation texts. Fails if the tokenizer configuration is invalid.
+pub fn cross_validate<T: AsRef<str>>(
+    factory: impl Fn(usize, usize) -> Box<dyn NaiveBayesClassifier>,
+    tokenizer_config: &TokenizerBuilder,
+    dataset: &[(T, usize)],
+    k: usize,
+    seed: u64,
+) -> Result<CrossValidation, RnbError> {
+    let n_labels = dataset
+        .iter()
+        .map(|(_, label)| label + 1)
+        .max()
+        .unwrap_or(0);
+
+    let folds = kfold(dataset.len(), k, seed)
+        .into_iter()
+        .map(|(training, validation)| {
+            let (tokenizer, classifier) =
+                fit_fold(&factory, tokenizer_config, dataset, &training, n_labels)?;
+
+            let mut matrix = ConfusionMatrix::new(n_labels);
+            for &idx in validation.iter() {
+                let (text, label) = &dataset[idx];
+                let predicted = classifier.predict(&tokenizer.tokenize_sparse(text.as_ref()));
+                matrix.add(predicted, *label);
+            }
+            Ok(matrix)
+        })
+        .collect::<Result<_, RnbError>>()?;
+
+    Ok(CrossValidation { folds })
+}
+
+/// Builds and fits a tokenizer and a classifier on the training samples of a fold.
+fn fit_fold<T: AsRef<str>>(
+    factory: &impl Fn(usize, usize) -> Box<dyn NaiveBayesClassifier>,
+    tokenizer_config: &TokenizerBuilder,
+    dataset: &[(T, usize)],
+    training: &[usize],
+    n_labels: usize,
+) -> Result<(Tokenizer, Box<dyn NaiveBayesClassifier>), RnbError> {
+    let texts: Vec<&str> = training
+        .iter()
+        .map(|&idx| dataset[idx].0.as_ref())
+        .collect();
+    let mut tokenizer = tokenizer_config.build()?;
+    tokenizer.fit_batch(&texts)?;
+    tokenizer.freeze();
+
+    let mut classifier = factory(tokenizer.token_count(), n_labels);
+    for (&idx, text) in training.iter().zip(texts) {
+        classifier.fit(&tokenizer.tokenize_sparse(text), dataset[idx].1);
+    }
+    Ok((tokenizer, classifier))
+}
+
+/// Leave-one-out cross-validates an unfitted classifier on the tokenized samples, validating every
+/// sample on the classifier fitted on all the other ones. Rather than refitting the classifier for
+/// every sample, it is fitted once then every sample is removed before predicting it and fitted
+/// back, leaving the classifier fitted on all samples.
+///
+/// The samples are tokenized beforehand, so the vocabulary of the tokenizer includes the tokens of
+/// each validated sample, unlike with [`cross_validate`].
+pub fn leave_one_out<C: DecrementalClassifier>(
+    classifier: &mut C,
+    samples: &[(Vec<usize>, usize)],
+) -> ConfusionMatrix {
+    let n_labels = samples
+        .iter()
+        .map(|(_, label)| label + 1)
+        .max()
+        .unwrap_or(0);
+
+    samples
+        .iter()
+        .for_each(|(tokens, label)| classifier.fit(tokens, *label));
+
+    let mut matrix = ConfusionMatrix::new(n_labels);
+    for (tokens, label) in samples {
+        classifier.unfit(tokens, *label);
+        matrix.add(classifier.predict(tokens), *label);
+        classifier.fit(tokens, *label);
+    }
+    matrix
+}
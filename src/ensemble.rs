@@ -0,0 +1,133 @@
+use rand::{rngs::StdRng, seq::index, SeedableRng};
+
+use crate::bayes::{decide, NaiveBayesClassifier, TieBreak};
+
+/// A member of the ensemble along with the subset of the vocabulary it is trained on.
+struct SubspaceMember<C: NaiveBayesClassifier> {
+    /// The member classifier, sized to its subspace.
+    classifier: C,
+    /// Maps every global feature index to its index in the member subspace, if any.
+    feature_map: Box<[Option<usize>]>,
+}
+
+impl<C: NaiveBayesClassifier> SubspaceMember<C> {
+    /// Projects the tokens onto the member subspace.
+    fn project(&self, tokens: &[usize]) -> Vec<usize> {
+        tokens
+            .iter()
+            .filter_map(|&token| self.feature_map.get(token).copied().flatten())
+            .collect()
+    }
+}
+
+/// An ensemble of Naive Bayes classifiers, each trained on a random subset of the vocabulary, whose
+/// probabilities are averaged.
+pub struct RandomSubspaceNB<C: NaiveBayesClassifier> {
+    members: Box<[SubspaceMember<C>]>,
+}
+
+impl<C: NaiveBayesClassifier> RandomSubspaceNB<C> {
+    /// Creates an ensemble of `n_members` classifiers, each built by `factory` given the number of
+    /// features of its subspace, and trained on `subspace_fraction` of the `n_features` vocabulary.
+    pub fn new(
+        n_features: usize,
+        n_members: usize,
+        subspace_fraction: f64,
+        seed: u64,
+        factory: impl Fn(usize) -> C,
+    ) -> Self {
+        assert!(n_members > 0, "Ensemble must have at least one member");
+        assert!(
+            subspace_fraction > 0.0 && subspace_fraction <= 1.0,
+            "Subspace fraction must be in the ]0, 1] range"
+        );
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let subspace_size = ((n_features as f64 * subspace_fraction).ceil() as usize).max(1);
+
+        let members = (0..n_members)
+            .map(|_| {
+                let mut subspace = index::sample(&mut rng, n_features, subspace_size).into_vec();
+                subspace.sort_unstable();
+
+                let mut feature_map = vec![None; n_features].into_boxed_slice();
+                subspace
+                    .iter()
+                    .enumerate()
+                    .for_each(|(local, &global)| feature_map[global] = Some(local));
+
+                SubspaceMember {
+                    classifier: factory(subspace.len()),
+                    feature_map,
+                }
+            })
+            .collect();
+
+        Self { members }
+    }
+
+    /// The number of classifiers in the ensemble.
+    #[inline]
+    pub fn member_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Averages a per-label score over all members.
+    fn average(&self, score: impl Fn(&C, &[usize]) -> Box<[f64]>, tokens: &[usize]) -> Box<[f64]> {
+        let mut total: Option<Box<[f64]>> = None;
+
+        for member in self.members.iter() {
+            let scores = score(&member.classifier, &member.project(tokens));
+            match total.as_mut() {
+                Some(total) => total
+                    .iter_mut()
+                    .zip(scores.iter())
+                    .for_each(|(t, s)| *t += s),
+                None => total = Some(scores),
+            }
+        }
+
+        let mut total = total.unwrap();
+        total
+            .iter_mut()
+            .for_each(|t| *t /= self.members.len() as f64);
+        total
+    }
+}
+
+impl<C: NaiveBayesClassifier> NaiveBayesClassifier for RandomSubspaceNB<C> {
+    /// Fits every member on the part of the tokenized text falling in its subspace.
+    fn fit(&mut self, tokens: &[usize], label: usize) {
+        for member in self.members.iter_mut() {
+            let projected = member.project(tokens);
+            member.classifier.fit(&projected, label);
+        }
+    }
+
+    /// Predicts the target label with the highest averaged probability for the tokenized text, the
+    /// lowest one among tied labels
+    fn predict(&self, tokens: &[usize]) -> usize {
+        decide(
+            &self.predict_probas(tokens),
+            None,
+            TieBreak::LowestIndex,
+            |_| 0.0,
+        )
+    }
+
+    /// Returns the target label probabilities averaged over all members
+    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.average(
+            |classifier, projected| classifier.predict_probas(projected),
+            tokens,
+        )
+    }
+
+    /// Returns the target label joint log-likelihoods averaged over all members
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.average(
+            |classifier, projected| classifier.joint_log_likelihood(projected),
+            tokens,
+        )
+    }
+}
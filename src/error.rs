@@ -0,0 +1,44 @@
+use std::fmt;
+
+/// Errors returned by the crate.
+#[derive(Debug)]
+pub enum RnbError {
+    /// The tokenizer vocabulary is frozen and can't be fitted anymore.
+    FrozenVocabulary,
+    /// A regex pattern is invalid.
+    InvalidRegex(regex::Error),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for RnbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RnbError::FrozenVocabulary => write!(f, "the tokenizer vocabulary is frozen"),
+            RnbError::InvalidRegex(err) => write!(f, "invalid regex: {}", err),
+            RnbError::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RnbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RnbError::FrozenVocabulary => None,
+            RnbError::InvalidRegex(err) => Some(err),
+            RnbError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<regex::Error> for RnbError {
+    fn from(err: regex::Error) -> Self {
+        RnbError::InvalidRegex(err)
+    }
+}
+
+impl From<std::io::Error> for RnbError {
+    fn from(err: std::io::Error) -> Self {
+        RnbError::Io(err)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{error::RnbError, remap::FeatureRemap, tokenizer::Tokenizer};
+
+/// Computes the chi-square statistic between every feature and the labels, from (tokens, label)
+/// samples. Higher scores indicate features whose counts depend more on the label.
+pub fn chi2_scores<'a>(
+    samples: impl IntoIterator<Item = (&'a [usize], usize)>,
+    n_features: usize,
+) -> Box<[f64]> {
+    let mut observed: Vec<Box<[f64]>> = Vec::new();
+    let mut label_counts: Vec<f64> = Vec::new();
+
+    for (tokens, label) in samples {
+        if label >= observed.len() {
+            observed.resize(label + 1, vec![0.0; n_features].into_boxed_slice());
+            label_counts.resize(label + 1, 0.0);
+        }
+
+        tokens
+            .iter()
+            .filter(|&&token| token < n_features)
+            .for_each(|&token| observed[label][token] += 1.0);
+        label_counts[label] += 1.0;
+    }
+
+    let n_samples: f64 = label_counts.iter().sum();
+
+    (0..n_features)
+        .map(|token| {
+            let feature_total: f64 = observed.iter().map(|counts| counts[token]).sum();
+
+            observed
+                .iter()
+                .zip(label_counts.iter())
+                .map(|(counts, label_count)| {
+                    let expected = label_count / n_samples * feature_total;
+                    if expected > 0.0 {
+                        (counts[token] - expected).powi(2) / expected
+                    } else {
+                        0.0
+                    }
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Keeps the `k` features with the highest chi-square score on the training texts in the tokenizer
+/// vocabulary, so that the classifier is trained on the reduced vocabulary. Special tokens and the
+/// unknown word token are always kept and count towards `k`.
+/// Returns the remapping applied to the tokenizer, or fails if its vocabulary is frozen.
+pub fn select_k_best<'a>(
+    tokenizer: &mut Tokenizer,
+    samples: impl IntoIterator<Item = (&'a str, usize)>,
+    k: usize,
+) -> Result<FeatureRemap, RnbError> {
+    let tokenized: Vec<(Vec<usize>, usize)> = samples
+        .into_iter()
+        .map(|(text, label)| (tokenizer.tokenize(text), label))
+        .collect();
+
+    let mut scores = chi2_scores(
+        tokenized
+            .iter()
+            .map(|(tokens, label)| (tokens.as_slice(), *label)),
+        tokenizer.token_count(),
+    );
+
+    scores
+        .iter_mut()
+        .enumerate()
+        .filter(|(idx, _)| tokenizer.is_reserved(*idx))
+        .for_each(|(_, score)| *score = f64::INFINITY);
+
+    let remap = FeatureRemap::top_k(&scores, k);
+    tokenizer.remap(&remap)?;
+    Ok(remap)
+}
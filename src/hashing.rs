@@ -0,0 +1,90 @@
+use serde::{de::Error, Deserialize, Serialize};
+use std::io::Read;
+
+use crate::tokenizer::Tokenizer;
+
+/// A tokenizer mapping terms to tokens by hashing them into a fixed number of features instead of
+/// storing a vocabulary, so that it needs no fitting and handles unbounded vocabularies.
+///
+/// Distinct terms may collide into the same token, which is rare as long as the number of features
+/// is large compared to the number of distinct terms.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HashingTokenizer {
+    /// The tokenizer whose normalization and term extraction settings are used. Its vocabulary is
+    /// unused.
+    analyzer: Tokenizer,
+    /// Number of features the terms are hashed into.
+    n_features: usize,
+}
+
+impl HashingTokenizer {
+    /// Creates a hashing tokenizer splitting texts into terms as the configured `analyzer` does, and
+    /// hashing them into `n_features` features.
+    pub fn new(analyzer: Tokenizer, n_features: usize) -> Self {
+        assert!(n_features > 0, "Number of features must be non-zero");
+        Self {
+            analyzer,
+            n_features,
+        }
+    }
+
+    /// Loads a tokenizer from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves a tokenizer to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// Tokenize the supplied text into a list of tokens.
+    pub fn tokenize(&self, text: &str) -> Vec<usize> {
+        let mut tokens = Vec::new();
+        self.tokenize_into(text, &mut tokens);
+        tokens
+    }
+
+    /// Tokenize the supplied text into a vector representing the presence of words.
+    pub fn tokenize_sparse(&self, text: &str) -> Vec<usize> {
+        let mut tokens = Vec::new();
+        self.tokenize_sparse_into(text, &mut tokens);
+        tokens
+    }
+
+    /// Tokenize the supplied text into a list of tokens written to `out`, which is cleared first so
+    /// that its allocation can be reused across texts.
+    pub fn tokenize_into(&self, text: &str, out: &mut Vec<usize>) {
+        out.clear();
+        self.analyzer
+            .for_each_term(text, |term| out.push(self.hash(term)));
+    }
+
+    /// Tokenize the supplied text into a vector representing the presence of words written to
+    /// `out`, which is cleared first so that its allocation can be reused across texts.
+    pub fn tokenize_sparse_into(&self, text: &str, out: &mut Vec<usize>) {
+        self.tokenize_into(text, out);
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    /// Maps the term to its token using the 64-bit FNV-1a hash, which is stable across platforms and
+    /// versions so that saved models stay valid.
+    fn hash(&self, term: &str) -> usize {
+        let hash = term.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        (hash % self.n_features as u64) as usize
+    }
+
+    /// The total count of tokens
+    #[inline]
+    pub fn token_count(&self) -> usize {
+        self.n_features
+    }
+}
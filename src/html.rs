@@ -0,0 +1,75 @@
+use regex::{Captures, Regex};
+use std::{borrow::Cow, sync::LazyLock};
+
+/// Matches comments, scripts, styles and tags.
+static MARKUP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<!--.*?-->|<script\b.*?</script\s*>|<style\b.*?</style\s*>|</?[a-z!][^>]*>")
+        .unwrap()
+});
+
+/// Matches decimal, hexadecimal and named character references.
+static REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(?:#(\d+)|#[xX]([0-9a-fA-F]+)|([a-zA-Z]+));").unwrap());
+
+/// Strips the tags, comments, scripts and styles of an HTML text and decodes its character
+/// references. Tags are replaced with spaces so that the words they separate aren't glued together.
+pub(crate) fn strip_html(text: &str) -> Cow<'_, str> {
+    if !text.contains(['<', '&']) {
+        return Cow::Borrowed(text);
+    }
+
+    let stripped = MARKUP.replace_all(text, " ");
+    let decoded = REFERENCE.replace_all(&stripped, |caps: &Captures| {
+        let decoded = if let Some(decimal) = caps.get(1) {
+            decimal.as_str().parse().ok().and_then(char::from_u32)
+        } else if let Some(hex) = caps.get(2) {
+            u32::from_str_radix(hex.as_str(), 16)
+                .ok()
+                .and_then(char::from_u32)
+        } else {
+            named_entity(&caps[3])
+        };
+
+        decoded.map_or_else(|| caps[0].to_string(), String::from)
+    });
+
+    Cow::Owned(decoded.into_owned())
+}
+
+/// Returns the character of the most common named character references.
+fn named_entity(name: &str) -> Option<char> {
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "sect" => '§',
+        "deg" => '°',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "times" => '×',
+        "eacute" => 'é',
+        "egrave" => 'è',
+        "agrave" => 'à',
+        "ccedil" => 'ç',
+        _ => return None,
+    })
+}
@@ -0,0 +1,56 @@
+use indexmap::IndexSet;
+use serde::{de::Error, Deserialize, Serialize};
+use std::io::Read;
+
+/// Encodes human-readable class names into target label indices.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LabelEncoder {
+    /// The class names, indexed by their label.
+    classes: IndexSet<Box<str>>,
+}
+
+impl LabelEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a label encoder from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves a label encoder to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// Returns the label of the class name, adding it if it is unknown.
+    pub fn fit(&mut self, name: &str) -> usize {
+        self.classes.insert_full(name.into()).0
+    }
+
+    /// Returns the label of the class name, if known.
+    pub fn encode(&self, name: &str) -> Option<usize> {
+        self.classes.get_index_of(name)
+    }
+
+    /// Returns the class name of the label, if known.
+    pub fn decode(&self, label: usize) -> Option<&str> {
+        self.classes.get_index(label).map(|name| &**name)
+    }
+
+    /// Returns the number of known classes.
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Returns the class names, indexed by their label.
+    pub fn class_names(&self) -> Box<[Box<str>]> {
+        self.classes.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,24 @@
+//! Rusty Naïve Bayes classifiers.
+
+pub mod bayes;
+mod cache;
+pub mod calibration;
+pub mod compact;
+pub mod counts;
+pub mod cv;
+pub mod ensemble;
+pub mod error;
+pub mod feature_selection;
+pub mod hashing;
+mod html;
+pub mod labels;
+pub mod metrics;
+pub mod multi_field;
+pub mod preprocess;
+pub mod remap;
+pub mod semi_supervised;
+pub mod spelling;
+pub mod split;
+pub mod stopwords;
+pub mod tfidf;
+pub mod tokenizer;
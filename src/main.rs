@@ -1,69 +1,83 @@
-mod bayes;
-mod metrics;
-mod tokenizer;
 use std::{env, error::Error};
 
-use bayes::{BernouliNB, MultinomialNB, NaiveBayesClassifier};
 use parquet::{
     file::{reader::FileReader, serialized_reader::SerializedFileReader},
     record::RowAccessor,
 };
-use tokenizer::Tokenizer;
+use rnb::{
+    bayes::{BernouliNB, DecayingNB, MultinomialNB, NaiveBayesClassifier, SketchNB},
+    metrics, split,
+    tokenizer::Tokenizer,
+};
 
 #[derive(Debug)]
 enum Model {
     Bernoulli,
     Multinomial,
+    Decaying,
+    Sketch,
 }
 
-fn create_model(model: Model, n_features: usize) -> Box<dyn NaiveBayesClassifier> {
+fn create_model(model: Model, n_features: usize, n_labels: usize) -> Box<dyn NaiveBayesClassifier> {
     match model {
-        Model::Bernoulli => Box::new(BernouliNB::new(n_features, 2, 0.1)),
-        Model::Multinomial => Box::new(MultinomialNB::new(n_features, 2, 0.1)),
+        Model::Bernoulli => Box::new(BernouliNB::new(n_features, n_labels, 0.1)),
+        Model::Multinomial => Box::new(MultinomialNB::new(n_features, n_labels, 0.1)),
+        Model::Decaying => Box::new(DecayingNB::new(n_features, n_labels, 0.1, 0.999)),
+        Model::Sketch => Box::new(SketchNB::with_error_bounds(
+            n_features, n_labels, 0.1, 0.001, 0.01,
+        )),
     }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let used_model = env::args()
         .nth(1)
-        .map(|x| {
-            if x == "bernoulli" {
-                Model::Bernoulli
-            } else {
-                Model::Multinomial
-            }
+        .map(|x| match x.as_str() {
+            "bernoulli" => Model::Bernoulli,
+            "decaying" => Model::Decaying,
+            "sketch" => Model::Sketch,
+            _ => Model::Multinomial,
         })
         .unwrap_or(Model::Multinomial);
 
     println!("Using NB {:?} classifier", used_model);
 
     // Create the tokenizer
-    let mut toknzr = Tokenizer::new("([.,!?;:=()\"'\\[\\]1234567890/@#*‘&_])");
+    let mut toknzr = Tokenizer::new("([.,!?;:=()\"'\\[\\]1234567890/@#*‘&_])")?;
 
     // Parse the whole dataset and store it in memory
     let dataset_file = std::fs::File::open("datasets/sms_spam.parquet")?;
     let dataset: SerializedFileReader<std::fs::File> = SerializedFileReader::new(dataset_file)?;
 
-    let mut training_pairs: Vec<(String, usize)> = Vec::new();
+    let mut pairs: Vec<(String, usize)> = Vec::new();
     for row in dataset.get_row_iter(None)?.flatten() {
-        training_pairs.push((
+        pairs.push((
             row.get_string(0)?.replace("\n", ""),
             row.get_long(1)? as usize,
         ));
     }
 
-    // Split the dataset into a training and eval set
-    let eval_pairs: Vec<(String, usize)> = training_pairs.split_off(training_pairs.len() - 100);
+    // Split the dataset into a training and eval set with the same label proportions
+    let labels: Vec<usize> = pairs.iter().map(|row| row.1).collect();
+    let (training_indices, eval_indices) = split::stratified_shuffle_split(&labels, 0.2, 42);
+    let training_pairs: Vec<&(String, usize)> =
+        training_indices.iter().map(|&idx| &pairs[idx]).collect();
+    let eval_pairs: Vec<&(String, usize)> = eval_indices.iter().map(|&idx| &pairs[idx]).collect();
 
     // Fit the tokenizer on every training text pair
-    training_pairs.iter().for_each(|row| _ = toknzr.fit(&row.0));
+    let training_texts: Vec<&str> = training_pairs.iter().map(|row| row.0.as_str()).collect();
+    toknzr.fit_batch(&training_texts)?;
+    toknzr.freeze();
     toknzr.save_to_file(&mut std::fs::File::create("tokenizer.json")?)?;
 
     println!("Tokenizer vocab size: {}", toknzr.token_count());
 
+    // Infer the number of labels from the whole dataset, so that evaluation labels are in range
+    let n_labels = labels.iter().max().map_or(0, |x| x + 1);
+
     // Create the classifier based on provided program arguments
-    let mut nb = create_model(used_model, toknzr.token_count());
-    
+    let mut nb = create_model(used_model, toknzr.token_count(), n_labels);
+
     // Train the classifier on the training set
     training_pairs
         .iter()
@@ -76,10 +90,13 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let eval_labels = eval_pairs.iter().map(|x| x.1).collect::<Vec<usize>>();
 
-    let confusion_matrix = metrics::confusion_matrix(&eval_predicted[0..], &eval_labels[0..], 2);
-
-    println!("Eval. accuracy: {:.3}", confusion_matrix.accuracy());
-    println!("Eval. recall: {:.3}", confusion_matrix.recall(1));
+    // Name the classes after the labels found in the dataset
+    let class_names: Vec<String> = (0..n_labels).map(|label| label.to_string()).collect();
+    let class_names: Vec<&str> = class_names.iter().map(|name| name.as_str()).collect();
+    print!(
+        "{}",
+        metrics::classification_report(&eval_predicted, &eval_labels, &class_names)
+    );
 
     Ok(())
 }
@@ -1,18 +1,329 @@
-#[derive(Debug)]
-pub struct ConfusionMatrix(Box<[Box<[usize]>]>);
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfusionMatrix {
+    /// Sample counts indexed as `real * n_classes + predicted`.
+    matrix: Box<[usize]>,
+    /// Number of classes.
+    n_classes: usize,
+}
 
 impl ConfusionMatrix {
+    /// Creates an empty matrix over the classes, to be filled sample by sample.
+    pub fn new(n_classes: usize) -> Self {
+        Self {
+            matrix: vec![0usize; n_classes * n_classes].into_boxed_slice(),
+            n_classes,
+        }
+    }
+
+    /// Counts a sample of the real class predicted as the predicted class.
+    ///
+    /// Where [`std::ops::Add`] is imported, `matrix.add(predicted, real)` resolves to the merge of
+    /// two matrices instead, so this must be called as `ConfusionMatrix::add(&mut matrix, ..)`.
+    pub fn add(&mut self, predicted: usize, real: usize) {
+        assert!(
+            predicted < self.n_classes && real < self.n_classes,
+            "Labels must be below the number of classes"
+        );
+        self.matrix[real * self.n_classes + predicted] += 1;
+    }
+
     pub fn accuracy(&self) -> f64 {
-        let total_correct: usize = self.0.iter().enumerate().map(|(i, row)| row[i]).sum();
-        let total_samples: usize = self.0.iter().flatten().sum();
+        let total_correct: usize = (0..self.n_classes).map(|i| self.get(i, i)).sum();
+        let total_samples = self.total();
         total_correct as f64 / total_samples as f64
     }
 
     pub fn recall(&self, class: usize) -> f64 {
-        let true_positive = self.0[class][class];
-        let total_actual_positive: usize = self.0[class].iter().sum();
+        let true_positive = self.get(class, class);
+        let total_actual_positive = self.support(class);
         true_positive as f64 / total_actual_positive as f64
     }
+
+    /// Returns the fraction of the samples predicted as the class which really are of the class.
+    pub fn precision(&self, class: usize) -> f64 {
+        let true_positive = self.get(class, class);
+        let total_predicted_positive = self.predicted_total(class);
+        true_positive as f64 / total_predicted_positive as f64
+    }
+
+    /// Returns the harmonic mean of the precision and recall of the class.
+    pub fn f1(&self, class: usize) -> f64 {
+        self.f_beta(class, 1.0)
+    }
+
+    /// Returns the weighted harmonic mean of the precision and recall of the class, recall being
+    /// `beta` times as important as precision, e.g. 2 to favor catching spam over false alarms.
+    pub fn f_beta(&self, class: usize, beta: f64) -> f64 {
+        assert!(beta > 0.0, "Beta must be positive");
+        let beta2 = beta * beta;
+        let true_positive = self.get(class, class) as f64;
+        let false_negative = self.support(class) as f64 - true_positive;
+        let false_positive = self.predicted_total(class) as f64 - true_positive;
+        (1.0 + beta2) * true_positive
+            / ((1.0 + beta2) * true_positive + beta2 * false_negative + false_positive)
+    }
+
+    /// Returns the fraction of the samples not of the class which aren't predicted as the class.
+    pub fn specificity(&self, class: usize) -> f64 {
+        let total_samples = self.total();
+        let total_actual_negative = total_samples - self.support(class);
+        let false_positive = self.predicted_total(class) - self.get(class, class);
+        (total_actual_negative - false_positive) as f64 / total_actual_negative as f64
+    }
+
+    /// Returns the fraction of the samples not of the class which are predicted as the class.
+    pub fn fpr(&self, class: usize) -> f64 {
+        1.0 - self.specificity(class)
+    }
+
+    /// Returns the mean of the recall of the classes having samples, which unlike the accuracy isn't
+    /// inflated by the majority class on imbalanced datasets.
+    pub fn balanced_accuracy(&self) -> f64 {
+        let recalls: Vec<f64> = (0..self.n_classes)
+            .filter(|&class| self.support(class) > 0)
+            .map(|class| self.recall(class))
+            .collect();
+        recalls.iter().sum::<f64>() / recalls.len() as f64
+    }
+
+    /// Returns Youden's J statistic of the class, that is its recall plus its specificity minus 1,
+    /// which is 0 for a classifier no better than chance and 1 for a perfect one.
+    pub fn youdens_j(&self, class: usize) -> f64 {
+        self.recall(class) + self.specificity(class) - 1.0
+    }
+
+    /// Returns the geometric mean of the recall of the classes having samples, which is 0 as soon as
+    /// such a class is never recognized however well the others are.
+    pub fn g_mean(&self) -> f64 {
+        let recalls: Vec<f64> = (0..self.n_classes)
+            .filter(|&class| self.support(class) > 0)
+            .map(|class| self.recall(class))
+            .collect();
+        recalls
+            .iter()
+            .product::<f64>()
+            .powf(1.0 / recalls.len() as f64)
+    }
+
+    /// Returns Cohen's kappa, the agreement between the predicted and real classes corrected for the
+    /// agreement expected by chance given their class frequencies: 1 for perfect agreement and 0 for
+    /// chance-level agreement.
+    pub fn cohens_kappa(&self) -> f64 {
+        let total_samples = self.total() as f64;
+        let observed = self.accuracy();
+        let expected = (0..self.n_classes)
+            .map(|class| {
+                let real_total = self.support(class) as f64;
+                real_total * self.predicted_total(class) as f64
+            })
+            .sum::<f64>()
+            / (total_samples * total_samples);
+        (observed - expected) / (1.0 - expected)
+    }
+
+    /// Returns the unweighted mean of the precision of every class.
+    pub fn precision_macro(&self) -> f64 {
+        self.macro_average(Self::precision)
+    }
+
+    /// Returns the precision over the samples of every class, which is the accuracy since every
+    /// sample has a single predicted class.
+    pub fn precision_micro(&self) -> f64 {
+        self.accuracy()
+    }
+
+    /// Returns the mean of the precision of every class weighted by its support.
+    pub fn precision_weighted(&self) -> f64 {
+        self.weighted_average(Self::precision)
+    }
+
+    /// Returns the unweighted mean of the recall of every class.
+    pub fn recall_macro(&self) -> f64 {
+        self.macro_average(Self::recall)
+    }
+
+    /// Returns the recall over the samples of every class, which is the accuracy since every sample
+    /// has a single real class.
+    pub fn recall_micro(&self) -> f64 {
+        self.accuracy()
+    }
+
+    /// Returns the mean of the recall of every class weighted by its support.
+    pub fn recall_weighted(&self) -> f64 {
+        self.weighted_average(Self::recall)
+    }
+
+    /// Returns the unweighted mean of the F1 score of every class.
+    pub fn f1_macro(&self) -> f64 {
+        self.macro_average(Self::f1)
+    }
+
+    /// Returns the F1 score over the samples of every class, which is the accuracy as micro precision
+    /// and recall both are.
+    pub fn f1_micro(&self) -> f64 {
+        self.accuracy()
+    }
+
+    /// Returns the mean of the F1 score of every class weighted by its support.
+    pub fn f1_weighted(&self) -> f64 {
+        self.weighted_average(Self::f1)
+    }
+
+    /// Returns the unweighted mean of a per-class metric, undefined values counting as 0.
+    fn macro_average(&self, metric: fn(&Self, usize) -> f64) -> f64 {
+        let total: f64 = (0..self.n_classes)
+            .map(|class| defined_or_zero(metric(self, class)))
+            .sum();
+        total / self.n_classes as f64
+    }
+
+    /// Returns the mean of a per-class metric weighted by the number of samples of each class,
+    /// undefined values counting as 0.
+    fn weighted_average(&self, metric: fn(&Self, usize) -> f64) -> f64 {
+        let total: f64 = (0..self.n_classes)
+            .map(|class| {
+                let support = self.support(class);
+                match support {
+                    0 => 0.0,
+                    support => support as f64 * defined_or_zero(metric(self, class)),
+                }
+            })
+            .sum();
+        total / self.total() as f64
+    }
+
+    /// Returns the count of samples of the real class predicted as the predicted class.
+    #[inline]
+    pub fn get(&self, real: usize, predicted: usize) -> usize {
+        self.matrix[real * self.n_classes + predicted]
+    }
+
+    /// Returns the counts of samples of the real class for every predicted class.
+    #[inline]
+    pub fn row(&self, real: usize) -> &[usize] {
+        &self.matrix[real * self.n_classes..(real + 1) * self.n_classes]
+    }
+
+    /// Returns the count of samples of the real class.
+    pub fn support(&self, class: usize) -> usize {
+        self.row(class).iter().sum()
+    }
+
+    /// Returns the count of samples.
+    pub fn total(&self) -> usize {
+        self.matrix.iter().sum()
+    }
+
+    /// Returns the number of classes.
+    pub fn n_classes(&self) -> usize {
+        self.n_classes
+    }
+
+    /// Returns the count of samples predicted as the class, whatever their real class.
+    fn predicted_total(&self, predicted: usize) -> usize {
+        (0..self.n_classes)
+            .map(|real| self.get(real, predicted))
+            .sum()
+    }
+
+    /// Adds the counts of another matrix over the same classes, e.g. to aggregate the matrices of
+    /// cross-validation folds.
+    pub fn merge(&mut self, other: &ConfusionMatrix) {
+        assert_eq!(
+            self.n_classes, other.n_classes,
+            "Numbers of classes of merged matrices must match"
+        );
+        self.matrix
+            .iter_mut()
+            .zip(other.matrix.iter())
+            .for_each(|(count, other_count)| *count += other_count);
+    }
+
+    /// Returns a displayable version of the matrix with the rows and columns labelled by the class
+    /// names, indexed by their label, instead of the labels.
+    pub fn with_class_names<'a>(&'a self, class_names: &'a [&'a str]) -> impl fmt::Display + 'a {
+        assert_eq!(
+            class_names.len(),
+            self.n_classes,
+            "Number of class names must match the number of classes"
+        );
+        NamedConfusionMatrix {
+            matrix: self,
+            class_names,
+        }
+    }
+
+    /// Formats the matrix as a table with a row per real class and a column per predicted class.
+    fn fmt_table(&self, f: &mut fmt::Formatter<'_>, class_names: &[String]) -> fmt::Result {
+        const CORNER: &str = "real \\ predicted";
+        let name_width = class_names
+            .iter()
+            .map(|name| name.chars().count())
+            .chain(std::iter::once(CORNER.len()))
+            .max()
+            .unwrap_or_default();
+        let cell_width = class_names
+            .iter()
+            .map(|name| name.chars().count())
+            .chain(self.matrix.iter().map(|count| count.to_string().len()))
+            .max()
+            .unwrap_or_default();
+
+        write!(f, "{:<name_width$}", CORNER)?;
+        for name in class_names {
+            write!(f, " {:>cell_width$}", name)?;
+        }
+        writeln!(f)?;
+
+        for (real, name) in class_names.iter().enumerate() {
+            write!(f, "{:<name_width$}", name)?;
+            for count in self.row(real) {
+                write!(f, " {:>cell_width$}", count)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConfusionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = (0..self.n_classes).map(|label| label.to_string()).collect();
+        self.fmt_table(f, &labels)
+    }
+}
+
+impl std::ops::Add for ConfusionMatrix {
+    type Output = ConfusionMatrix;
+
+    fn add(mut self, other: ConfusionMatrix) -> ConfusionMatrix {
+        self.merge(&other);
+        self
+    }
+}
+
+/// A confusion matrix displayed with class names.
+struct NamedConfusionMatrix<'a> {
+    matrix: &'a ConfusionMatrix,
+    class_names: &'a [&'a str],
+}
+
+impl fmt::Display for NamedConfusionMatrix<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_names: Vec<String> = self.class_names.iter().map(|&n| n.into()).collect();
+        self.matrix.fmt_table(f, &class_names)
+    }
+}
+
+/// Returns the value, or 0 if it is undefined.
+fn defined_or_zero(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value
+    }
 }
 
 /// Compute the confusion matrix from a list of predicted and target labels.
@@ -27,12 +338,519 @@ pub fn confusion_matrix(
         "Lengths of predicted and real labels must match"
     );
 
-    let mut matrix =
-        vec![vec![0usize; num_classes].into_boxed_slice(); num_classes].into_boxed_slice();
+    let mut matrix = ConfusionMatrix::new(num_classes);
 
     for (&p, &r) in predicted.iter().zip(real.iter()) {
-        matrix[r][p] += 1;
+        matrix.add(p, r);
+    }
+
+    matrix
+}
+
+/// Precision, recall and F1 score of a class or an average of them, undefined scores counting as 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Number of samples of the class, or of every class for averages.
+    pub support: usize,
+}
+
+/// Per-class and averaged scores of a classifier, displayed as a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationReport {
+    /// The class names, indexed by their label.
+    pub class_names: Box<[Box<str>]>,
+    /// The scores of each class, indexed by its label.
+    pub classes: Box<[ClassScores]>,
+    pub accuracy: f64,
+    /// The unweighted mean of the class scores.
+    pub macro_avg: ClassScores,
+    /// The mean of the class scores weighted by their support.
+    pub weighted_avg: ClassScores,
+}
+
+impl ClassificationReport {
+    /// Computes the report from a list of predicted and target labels, with one name per class.
+    pub fn new(predicted: &[usize], real: &[usize], class_names: &[&str]) -> Self {
+        let matrix = confusion_matrix(predicted, real, class_names.len());
+        let total_samples = real.len();
+
+        Self {
+            class_names: class_names.iter().map(|&name| name.into()).collect(),
+            classes: (0..class_names.len())
+                .map(|class| ClassScores {
+                    precision: defined_or_zero(matrix.precision(class)),
+                    recall: defined_or_zero(matrix.recall(class)),
+                    f1: defined_or_zero(matrix.f1(class)),
+                    support: matrix.support(class),
+                })
+                .collect(),
+            accuracy: matrix.accuracy(),
+            macro_avg: ClassScores {
+                precision: matrix.precision_macro(),
+                recall: matrix.recall_macro(),
+                f1: matrix.f1_macro(),
+                support: total_samples,
+            },
+            weighted_avg: ClassScores {
+                precision: matrix.precision_weighted(),
+                recall: matrix.recall_weighted(),
+                f1: matrix.f1_weighted(),
+                support: total_samples,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ClassificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .class_names
+            .iter()
+            .map(|name| name.chars().count())
+            .chain(std::iter::once("weighted avg".len()))
+            .max()
+            .unwrap_or_default();
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, scores: &ClassScores| {
+            writeln!(
+                f,
+                "{:>width$} {:>9.3} {:>9.3} {:>9.3} {:>9}",
+                name, scores.precision, scores.recall, scores.f1, scores.support
+            )
+        };
+
+        writeln!(
+            f,
+            "{:>width$} {:>9} {:>9} {:>9} {:>9}",
+            "", "precision", "recall", "f1-score", "support"
+        )?;
+        writeln!(f)?;
+        for (name, scores) in self.class_names.iter().zip(self.classes.iter()) {
+            row(f, name, scores)?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>width$} {:>9} {:>9} {:>9.3} {:>9}",
+            "accuracy", "", "", self.accuracy, self.macro_avg.support
+        )?;
+        row(f, "macro avg", &self.macro_avg)?;
+        row(f, "weighted avg", &self.weighted_avg)
+    }
+}
+
+/// Formats a table of the precision, recall, F1 score and support of each class along with their
+/// averages from a list of predicted and target labels, with one name per class.
+pub fn classification_report(predicted: &[usize], real: &[usize], class_names: &[&str]) -> String {
+    ClassificationReport::new(predicted, real, class_names).to_string()
+}
+
+/// Points of a receiver operating characteristic curve, one per distinct decision threshold in
+/// decreasing order, starting at the infinite threshold predicting every sample as negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocCurve {
+    /// False positive rate of each threshold.
+    pub fpr: Box<[f64]>,
+    /// True positive rate (recall) of each threshold.
+    pub tpr: Box<[f64]>,
+    /// Scores above or equal to which samples are predicted as positive.
+    pub thresholds: Box<[f64]>,
+}
+
+/// Computes the ROC curve of the positive class scores of the samples, e.g. the positive class
+/// probabilities returned by `predict_probas`, and whether each sample is positive.
+pub fn roc_curve(scores: &[f64], labels: &[bool]) -> RocCurve {
+    let counts = threshold_counts(scores, labels);
+    let (positives, negatives) = counts.last().map_or((0, 0), |&(_, tp, fp)| (tp, fp));
+
+    let points: Vec<_> = std::iter::once((f64::INFINITY, 0, 0))
+        .chain(counts)
+        .collect();
+    RocCurve {
+        fpr: points
+            .iter()
+            .map(|&(_, _, fp)| fp as f64 / negatives as f64)
+            .collect(),
+        tpr: points
+            .iter()
+            .map(|&(_, tp, _)| tp as f64 / positives as f64)
+            .collect(),
+        thresholds: points.iter().map(|&(threshold, _, _)| threshold).collect(),
+    }
+}
+
+/// Computes the area under the ROC curve of the positive class scores of the samples and whether
+/// each sample is positive, which is the probability that a random positive sample scores higher
+/// than a random negative one.
+pub fn roc_auc(scores: &[f64], labels: &[bool]) -> f64 {
+    let curve = roc_curve(scores, labels);
+    trapezoid_area(&curve.fpr, &curve.tpr)
+}
+
+/// Points of a precision-recall curve, one per distinct decision threshold in decreasing order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrCurve {
+    /// Precision of each threshold.
+    pub precision: Box<[f64]>,
+    /// Recall of each threshold.
+    pub recall: Box<[f64]>,
+    /// Scores above or equal to which samples are predicted as positive.
+    pub thresholds: Box<[f64]>,
+}
+
+/// Computes the precision-recall curve of the positive class scores of the samples and whether
+/// each sample is positive. Unlike the ROC curve, it isn't dominated by the easy negatives of
+/// heavily imbalanced datasets.
+pub fn pr_curve(scores: &[f64], labels: &[bool]) -> PrCurve {
+    let counts = threshold_counts(scores, labels);
+    let positives = counts.last().map_or(0, |&(_, tp, _)| tp);
+
+    PrCurve {
+        precision: counts
+            .iter()
+            .map(|&(_, tp, fp)| tp as f64 / (tp + fp) as f64)
+            .collect(),
+        recall: counts
+            .iter()
+            .map(|&(_, tp, _)| tp as f64 / positives as f64)
+            .collect(),
+        thresholds: counts.iter().map(|&(threshold, _, _)| threshold).collect(),
     }
+}
+
+/// Computes the average precision of the positive class scores of the samples and whether each
+/// sample is positive, that is the mean of the precisions of the thresholds weighted by their
+/// recall increase, summarizing the precision-recall curve without interpolation.
+pub fn average_precision(scores: &[f64], labels: &[bool]) -> f64 {
+    let curve = pr_curve(scores, labels);
+    let mut previous_recall = 0.0;
+    curve
+        .precision
+        .iter()
+        .zip(curve.recall.iter())
+        .map(|(&precision, &recall)| {
+            let increase = recall - std::mem::replace(&mut previous_recall, recall);
+            increase * precision
+        })
+        .sum()
+}
+
+/// Computes the mean cross-entropy between the class probabilities of the samples, e.g. returned
+/// by `predict_probas`, and their real class. Probabilities are clipped away from 0 and 1 so that a
+/// confidently wrong prediction has a large but finite loss.
+pub fn log_loss<P: AsRef<[f64]>>(probas: &[P], real: &[usize]) -> f64 {
+    const EPSILON: f64 = 1e-15;
+    assert_eq!(
+        probas.len(),
+        real.len(),
+        "Lengths of probabilities and real labels must match"
+    );
+
+    let total: f64 = probas
+        .iter()
+        .zip(real.iter())
+        .map(|(probas, &r)| -probas.as_ref()[r].clamp(EPSILON, 1.0 - EPSILON).ln())
+        .sum();
+    total / real.len() as f64
+}
+
+/// Computes the fraction of the samples whose real class is among the `k` most probable classes
+/// given their class probabilities, e.g. returned by `predict_probas`. Classes tied with the real
+/// class don't push it out of the `k` best.
+pub fn top_k_accuracy<P: AsRef<[f64]>>(probas: &[P], real: &[usize], k: usize) -> f64 {
+    assert_eq!(
+        probas.len(),
+        real.len(),
+        "Lengths of probabilities and real labels must match"
+    );
+    assert!(k > 0, "K must be non-zero");
 
-    ConfusionMatrix(matrix)
+    let correct = probas
+        .iter()
+        .zip(real.iter())
+        .filter(|(probas, &r)| {
+            let probas = probas.as_ref();
+            probas.iter().filter(|&&p| p > probas[r]).count() < k
+        })
+        .count();
+    correct as f64 / real.len() as f64
+}
+
+/// Computes the mean squared difference between the positive class probabilities of the samples
+/// and whether each sample is positive, measuring both calibration and sharpness.
+pub fn brier_score(probas: &[f64], labels: &[bool]) -> f64 {
+    assert_eq!(
+        probas.len(),
+        labels.len(),
+        "Lengths of probabilities and labels must match"
+    );
+
+    let total: f64 = probas
+        .iter()
+        .zip(labels.iter())
+        .map(|(&p, &positive)| (p - if positive { 1.0 } else { 0.0 }).powi(2))
+        .sum();
+    total / labels.len() as f64
+}
+
+/// Reliability diagram of positive class probabilities, with one point per non-empty bin of equal
+/// width over `[0, 1]`, in increasing probability order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationCurve {
+    /// Mean predicted probability of the samples of each bin.
+    pub mean_predicted: Box<[f64]>,
+    /// Fraction of positive samples of each bin, equal to the mean predicted probability for a
+    /// perfectly calibrated classifier.
+    pub fraction_positive: Box<[f64]>,
+    /// Number of samples of each bin.
+    pub counts: Box<[usize]>,
+}
+
+/// Computes the calibration curve of the positive class probabilities of the samples and whether
+/// each sample is positive, binning the probabilities into `n_bins` bins of equal width.
+pub fn calibration_curve(probas: &[f64], labels: &[bool], n_bins: usize) -> CalibrationCurve {
+    assert_eq!(
+        probas.len(),
+        labels.len(),
+        "Lengths of probabilities and labels must match"
+    );
+    assert!(n_bins > 0, "Number of bins must be non-zero");
+
+    // Sum of the probabilities, positive count and sample count of each bin.
+    let mut bins = vec![(0.0, 0usize, 0usize); n_bins];
+    for (&p, &positive) in probas.iter().zip(labels.iter()) {
+        let bin = ((p * n_bins as f64) as usize).min(n_bins - 1);
+        bins[bin].0 += p;
+        bins[bin].1 += positive as usize;
+        bins[bin].2 += 1;
+    }
+    bins.retain(|&(_, _, count)| count > 0);
+
+    CalibrationCurve {
+        mean_predicted: bins
+            .iter()
+            .map(|&(sum, _, count)| sum / count as f64)
+            .collect(),
+        fraction_positive: bins
+            .iter()
+            .map(|&(_, positives, count)| positives as f64 / count as f64)
+            .collect(),
+        counts: bins.iter().map(|&(_, _, count)| count).collect(),
+    }
+}
+
+/// Computes the expected calibration error of the positive class probabilities of the samples and
+/// whether each sample is positive, that is the mean gap between the predicted probability and
+/// the fraction of positive samples of the `n_bins` bins of equal width, weighted by their size.
+pub fn ece(probas: &[f64], labels: &[bool], n_bins: usize) -> f64 {
+    let curve = calibration_curve(probas, labels, n_bins);
+    let total: f64 = curve
+        .mean_predicted
+        .iter()
+        .zip(curve.fraction_positive.iter())
+        .zip(curve.counts.iter())
+        .map(|((&predicted, &positive), &count)| count as f64 * (predicted - positive).abs())
+        .sum();
+    total / labels.len() as f64
+}
+
+/// Lift and cumulative gains table, with one row per bin of samples of equal size in decreasing
+/// score order, e.g. deciles of a spam triage queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiftCurve {
+    /// Number of samples of each bin.
+    pub counts: Box<[usize]>,
+    /// Number of positive samples of each bin.
+    pub positives: Box<[usize]>,
+    /// Ratio of the positive rate of each bin to the overall positive rate.
+    pub lift: Box<[f64]>,
+    /// Fraction of the positive samples within each bin and the bins before it.
+    pub cumulative_gain: Box<[f64]>,
+    /// Ratio of the positive rate of each bin and the bins before it to the overall positive rate.
+    pub cumulative_lift: Box<[f64]>,
+}
+
+/// Computes the lift and cumulative gains of the positive class scores of the samples and whether
+/// each sample is positive, splitting the samples sorted by decreasing score into `n_bins` bins.
+pub fn lift_curve(scores: &[f64], labels: &[bool], n_bins: usize) -> LiftCurve {
+    assert_eq!(
+        scores.len(),
+        labels.len(),
+        "Lengths of scores and labels must match"
+    );
+    assert!(
+        n_bins > 0 && n_bins <= scores.len(),
+        "Number of bins must be non-zero and at most the number of samples"
+    );
+
+    let mut samples: Vec<(f64, bool)> =
+        scores.iter().copied().zip(labels.iter().copied()).collect();
+    samples.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let n_samples = samples.len();
+    let counts: Vec<usize> = (0..n_bins)
+        .map(|bin| (bin + 1) * n_samples / n_bins - bin * n_samples / n_bins)
+        .collect();
+    let positives: Vec<usize> = (0..n_bins)
+        .map(|bin| {
+            samples[bin * n_samples / n_bins..(bin + 1) * n_samples / n_bins]
+                .iter()
+                .filter(|(_, positive)| *positive)
+                .count()
+        })
+        .collect();
+
+    let total_positives: usize = positives.iter().sum();
+    let positive_rate = total_positives as f64 / n_samples as f64;
+    let (mut seen, mut seen_positives) = (0, 0);
+    let (cumulative_gain, cumulative_lift) = counts
+        .iter()
+        .zip(positives.iter())
+        .map(|(&count, &bin_positives)| {
+            seen += count;
+            seen_positives += bin_positives;
+            let gain = seen_positives as f64 / total_positives as f64;
+            let lift = seen_positives as f64 / seen as f64 / positive_rate;
+            (gain, lift)
+        })
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+
+    LiftCurve {
+        lift: counts
+            .iter()
+            .zip(positives.iter())
+            .map(|(&count, &bin_positives)| bin_positives as f64 / count as f64 / positive_rate)
+            .collect(),
+        counts: counts.into(),
+        positives: positives.into(),
+        cumulative_gain: cumulative_gain.into(),
+        cumulative_lift: cumulative_lift.into(),
+    }
+}
+
+/// Points of a detection error tradeoff curve, one per distinct decision threshold in decreasing
+/// order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetCurve {
+    /// False positive rate of each threshold.
+    pub fpr: Box<[f64]>,
+    /// False negative rate (miss rate) of each threshold.
+    pub fnr: Box<[f64]>,
+    /// Scores above or equal to which samples are predicted as positive.
+    pub thresholds: Box<[f64]>,
+}
+
+/// Computes the DET curve of the positive class scores of the samples and whether each sample is
+/// positive, trading false negatives for false positives. Usually plotted on normal deviate scales,
+/// it spreads out the low error rate operating points which ROC curves compress.
+pub fn det_curve(scores: &[f64], labels: &[bool]) -> DetCurve {
+    let counts = threshold_counts(scores, labels);
+    let (positives, negatives) = counts.last().map_or((0, 0), |&(_, tp, fp)| (tp, fp));
+
+    DetCurve {
+        fpr: counts
+            .iter()
+            .map(|&(_, _, fp)| fp as f64 / negatives as f64)
+            .collect(),
+        fnr: counts
+            .iter()
+            .map(|&(_, tp, _)| (positives - tp) as f64 / positives as f64)
+            .collect(),
+        thresholds: counts.iter().map(|&(threshold, _, _)| threshold).collect(),
+    }
+}
+
+/// Computes the Kolmogorov-Smirnov statistic of the positive class scores of the samples and
+/// whether each sample is positive, that is the maximal distance between the score distributions
+/// of the positive and negative samples: 0 when they overlap and 1 when perfectly separated.
+pub fn ks_statistic(scores: &[f64], labels: &[bool]) -> f64 {
+    let curve = roc_curve(scores, labels);
+    curve
+        .tpr
+        .iter()
+        .zip(curve.fpr.iter())
+        .map(|(tpr, fpr)| (tpr - fpr).abs())
+        .fold(0.0, f64::max)
+}
+
+/// What the decision threshold found by [`best_threshold`] optimizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdObjective {
+    /// Maximizes the F1 score of the positive class.
+    F1,
+    /// Maximizes the recall among the thresholds reaching the target precision.
+    Precision(f64),
+    /// Maximizes the precision among the thresholds reaching the target recall.
+    Recall(f64),
+}
+
+/// Sweeps the decision thresholds of the positive class scores of the samples and whether each
+/// sample is positive, returning the one best meeting the objective, if any. For positive class
+/// probabilities, the threshold can be stored back as the decision threshold of the classifier.
+pub fn best_threshold(
+    scores: &[f64],
+    labels: &[bool],
+    objective: ThresholdObjective,
+) -> Option<f64> {
+    let counts = threshold_counts(scores, labels);
+    let positives = counts.last().map_or(0, |&(_, tp, _)| tp);
+    let scored = counts.iter().filter_map(|&(threshold, tp, fp)| {
+        let precision = tp as f64 / (tp + fp) as f64;
+        let recall = tp as f64 / positives as f64;
+        let score = match objective {
+            ThresholdObjective::F1 => 2.0 * tp as f64 / (tp + fp + positives) as f64,
+            ThresholdObjective::Precision(target) => (precision >= target).then_some(recall)?,
+            ThresholdObjective::Recall(target) => (recall >= target).then_some(precision)?,
+        };
+        (!score.is_nan()).then_some((threshold, score))
+    });
+
+    // Ties are broken towards the highest threshold, which comes first.
+    scored
+        .fold(
+            None,
+            |best: Option<(f64, f64)>, (threshold, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((threshold, score)),
+            },
+        )
+        .map(|(threshold, _)| threshold)
+}
+
+/// Returns the area under the piecewise linear curve through the points.
+fn trapezoid_area(x: &[f64], y: &[f64]) -> f64 {
+    x.windows(2)
+        .zip(y.windows(2))
+        .map(|(x, y)| (x[1] - x[0]) * (y[0] + y[1]) / 2.0)
+        .sum()
+}
+
+/// Returns the true and false positive counts when predicting as positive the samples scoring
+/// above or equal to each distinct score, in decreasing score order.
+fn threshold_counts(scores: &[f64], labels: &[bool]) -> Vec<(f64, usize, usize)> {
+    assert_eq!(
+        scores.len(),
+        labels.len(),
+        "Lengths of scores and labels must match"
+    );
+
+    let mut samples: Vec<(f64, bool)> =
+        scores.iter().copied().zip(labels.iter().copied()).collect();
+    samples.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut counts: Vec<(f64, usize, usize)> = Vec::new();
+    let (mut tp, mut fp) = (0, 0);
+    for (i, &(score, positive)) in samples.iter().enumerate() {
+        if positive {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        if samples.get(i + 1).is_none_or(|next| next.0 != score) {
+            counts.push((score, tp, fp));
+        }
+    }
+    counts
 }
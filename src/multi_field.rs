@@ -0,0 +1,137 @@
+use indexmap::IndexSet;
+use serde::{de::Error, Deserialize, Serialize};
+use std::io::Read;
+
+use crate::{error::RnbError, tokenizer::Tokenizer};
+
+/// A tokenizer for documents made of several named fields, such as the subject and body of an
+/// email, whose terms make up disjoint feature spaces.
+///
+/// The fields share a single vocabulary in which the terms of a field are prefixed with the field
+/// name and [`Self::SEPARATOR`], e.g. `subject:free`, so that the same word gets a distinct token
+/// in every field while indices stay stable as the vocabulary grows.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiFieldTokenizer {
+    /// The tokenizer splitting the fields into terms and holding the shared vocabulary.
+    tokenizer: Tokenizer,
+    /// The names of the fields.
+    fields: IndexSet<Box<str>>,
+}
+
+impl MultiFieldTokenizer {
+    /// Separator between the field name and the term in the vocabulary.
+    pub const SEPARATOR: char = ':';
+
+    /// Creates a multi-field tokenizer splitting the fields into terms as the configured `tokenizer`
+    /// does. Field names must be unique and can't contain [`Self::SEPARATOR`].
+    pub fn new<'a>(tokenizer: Tokenizer, fields: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut names = IndexSet::new();
+        for field in fields {
+            assert!(
+                !field.contains(Self::SEPARATOR),
+                "Field names can't contain the separator"
+            );
+            assert!(names.insert(field.into()), "Field names must be unique");
+        }
+
+        Self {
+            tokenizer,
+            fields: names,
+        }
+    }
+
+    /// Loads a tokenizer from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves a tokenizer to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// Returns the names of the fields.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|field| &**field)
+    }
+
+    /// Returns the tokenizer holding the shared vocabulary.
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Returns the tokenizer holding the shared vocabulary, e.g. to freeze it.
+    pub fn tokenizer_mut(&mut self) -> &mut Tokenizer {
+        &mut self.tokenizer
+    }
+
+    /// Fits the tokenizer on the (field, text) pairs of a document and returns the tokens of the
+    /// document. Fails if the vocabulary is frozen.
+    pub fn fit(&mut self, document: &[(&str, &str)]) -> Result<Vec<usize>, RnbError> {
+        let mut tokens = Vec::new();
+        for &(field, text) in document {
+            let prefix = self.prefix(field);
+            tokens.extend(self.tokenizer.fit_prefixed(text, &prefix)?);
+        }
+        Ok(tokens)
+    }
+
+    /// Tokenize the (field, text) pairs of a document into a list of tokens.
+    pub fn tokenize(&self, document: &[(&str, &str)]) -> Vec<usize> {
+        let mut tokens = Vec::new();
+        self.tokenize_into(document, &mut tokens);
+        tokens
+    }
+
+    /// Tokenize the (field, text) pairs of a document into a vector representing the presence of
+    /// words.
+    pub fn tokenize_sparse(&self, document: &[(&str, &str)]) -> Vec<usize> {
+        let mut tokens = self.tokenize(document);
+        tokens.sort_unstable();
+        tokens.dedup();
+        tokens
+    }
+
+    /// Tokenize the (field, text) pairs of a document into a list of tokens written to `out`, which
+    /// is cleared first so that its allocation can be reused across documents.
+    pub fn tokenize_into(&self, document: &[(&str, &str)], out: &mut Vec<usize>) {
+        out.clear();
+        let mut term = String::new();
+        for &(field, text) in document {
+            let prefix = self.prefix(field);
+            self.tokenizer.for_each_term(text, |unprefixed| {
+                term.clear();
+                term.push_str(&prefix);
+                term.push_str(unprefixed);
+                out.extend(self.tokenizer.lookup(&term));
+            });
+        }
+    }
+
+    /// Returns the field and the unprefixed term of the token at the specified index, if any.
+    /// Special tokens have no field.
+    pub fn token(&self, idx: usize) -> Option<(Option<&str>, &str)> {
+        let token = self.tokenizer.token(idx)?;
+        Some(match token.split_once(Self::SEPARATOR) {
+            Some((field, term)) if self.fields.contains(field) => (Some(field), term),
+            _ => (None, token),
+        })
+    }
+
+    /// The total count of tokens
+    #[inline]
+    pub fn token_count(&self) -> usize {
+        self.tokenizer.token_count()
+    }
+
+    /// Returns the vocabulary prefix of the terms of the field.
+    fn prefix(&self, field: &str) -> String {
+        assert!(self.fields.contains(field), "Unknown field {:?}", field);
+        format!("{}{}", field, Self::SEPARATOR)
+    }
+}
@@ -0,0 +1,102 @@
+//! Composable text preprocessing stages, chained by
+//! [`crate::tokenizer::Tokenizer::set_preprocessors`].
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    error::RnbError,
+    html::strip_html,
+    tokenizer::{
+        collapse_repeats, regex_serde, CaseLocale, EmojiMode, Placeholder, UnicodeNormalization,
+    },
+};
+
+/// A stage transforming a text before it is split into words. Besides the built-in
+/// [`Preprocessor`] stages, user-defined stages can be added to a tokenizer with
+/// [`crate::tokenizer::Tokenizer::add_preprocessor`].
+pub trait TextPreprocessor {
+    /// Processes the text, only allocating if the text changes.
+    fn process<'t>(&self, text: &'t str) -> Cow<'t, str>;
+}
+
+impl fmt::Debug for dyn TextPreprocessor + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TextPreprocessor")
+    }
+}
+
+/// A compiled regex, compared and serialized as its pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pattern(#[serde(with = "regex_serde")] Regex);
+
+impl Pattern {
+    /// Compiles the pattern. Returns an error if the regex is invalid.
+    pub fn new(pattern: &str) -> Result<Self, RnbError> {
+        Ok(Self(Regex::new(pattern)?))
+    }
+
+    /// Returns the source of the pattern.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// A serializable preprocessing stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Preprocessor {
+    /// Case folds the text using the rules of the locale.
+    Lowercase(CaseLocale),
+    /// Strips HTML tags, comments, scripts and styles and decodes character references.
+    StripHtml,
+    /// Applies a Unicode normalization.
+    UnicodeNormalization(UnicodeNormalization),
+    /// Replaces a kind of text span with its placeholder token.
+    Placeholder(Placeholder),
+    /// Collapses runs of a repeated character, as
+    /// [`crate::tokenizer::Tokenizer::set_collapse_repeats`] does.
+    CollapseRepeats,
+    /// Splits emoji into standalone tokens.
+    Emoji(EmojiMode),
+    /// Replaces every match of the pattern with the replacement, which may refer to capture groups
+    /// as `$1` or `$name`.
+    Replace {
+        pattern: Pattern,
+        replacement: Box<str>,
+    },
+}
+
+impl Preprocessor {
+    /// Creates a stage replacing every match of the pattern with the replacement. Returns an error
+    /// if the regex is invalid.
+    pub fn replace(pattern: &str, replacement: &str) -> Result<Self, RnbError> {
+        Ok(Preprocessor::Replace {
+            pattern: Pattern::new(pattern)?,
+            replacement: replacement.into(),
+        })
+    }
+}
+
+impl TextPreprocessor for Preprocessor {
+    fn process<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match self {
+            Preprocessor::Lowercase(case_locale) => case_locale.fold_case(text),
+            Preprocessor::StripHtml => strip_html(text),
+            Preprocessor::UnicodeNormalization(normalization) => normalization.apply(text),
+            Preprocessor::Placeholder(placeholder) => placeholder.replace(text),
+            Preprocessor::CollapseRepeats => collapse_repeats(text),
+            Preprocessor::Emoji(emoji_mode) => emoji_mode.apply(text),
+            Preprocessor::Replace {
+                pattern,
+                replacement,
+            } => pattern.0.replace_all(text, &**replacement),
+        }
+    }
+}
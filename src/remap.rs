@@ -0,0 +1,61 @@
+/// A mapping from the feature indices of a vocabulary to the indices of a reduced vocabulary, used to
+/// keep tokenizers and classifiers consistent after dropping features.
+#[derive(Debug, Clone)]
+pub struct FeatureRemap {
+    /// The new index of every old feature, if it was kept.
+    mapping: Box<[Option<usize>]>,
+    /// The old index of every kept feature, in new index order.
+    kept: Box<[usize]>,
+}
+
+impl FeatureRemap {
+    /// Creates a remapping of `n_features` features keeping those for which `keep` returns true, in
+    /// their original order.
+    pub fn new(n_features: usize, keep: impl Fn(usize) -> bool) -> Self {
+        let kept: Box<[usize]> = (0..n_features).filter(|&idx| keep(idx)).collect();
+        let mut mapping = vec![None; n_features].into_boxed_slice();
+        kept.iter()
+            .enumerate()
+            .for_each(|(new, &old)| mapping[old] = Some(new));
+
+        Self { mapping, kept }
+    }
+
+    /// Creates a remapping keeping the `k` features with the highest scores, in their original order.
+    pub fn top_k(scores: &[f64], k: usize) -> Self {
+        let mut ranked: Vec<usize> = (0..scores.len()).collect();
+        ranked.sort_by(|&a, &b| {
+            scores[b]
+                .partial_cmp(&scores[a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut keep = vec![false; scores.len()];
+        ranked.into_iter().take(k).for_each(|idx| keep[idx] = true);
+        Self::new(scores.len(), |idx| keep[idx])
+    }
+
+    /// Returns the new index of the old feature, if it was kept.
+    #[inline]
+    pub fn get(&self, old: usize) -> Option<usize> {
+        self.mapping.get(old).copied().flatten()
+    }
+
+    /// The number of features before remapping.
+    #[inline]
+    pub fn old_n_features(&self) -> usize {
+        self.mapping.len()
+    }
+
+    /// The number of features after remapping.
+    #[inline]
+    pub fn n_features(&self) -> usize {
+        self.kept.len()
+    }
+
+    /// The old indices of the kept features, in new index order.
+    #[inline]
+    pub fn kept(&self) -> &[usize] {
+        &self.kept
+    }
+}
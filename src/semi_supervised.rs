@@ -0,0 +1,103 @@
+use crate::bayes::{decide, NaiveBayesClassifier, TieBreak};
+
+/// A semi-supervised wrapper which iteratively pseudo-labels the high-confidence unlabeled samples
+/// using the wrapped classifier and fits it on them.
+///
+/// Since Naive Bayes counts are additive, fitting the pseudo-labeled samples on top of the already
+/// fitted ones is equivalent to refitting the base classifier from scratch.
+pub struct SelfTraining<C: NaiveBayesClassifier> {
+    /// The wrapped base classifier.
+    classifier: C,
+    /// The minimal predicted probability for an unlabeled sample to be pseudo-labeled.
+    threshold: f64,
+    /// The maximal number of pseudo-labeling iterations.
+    max_iterations: usize,
+}
+
+impl<C: NaiveBayesClassifier> SelfTraining<C> {
+    pub fn new(classifier: C, threshold: f64, max_iterations: usize) -> Self {
+        Self {
+            classifier,
+            threshold,
+            max_iterations,
+        }
+    }
+
+    /// Fits the classifier on the labeled samples, then iteratively on the confidently pseudo-labeled
+    /// unlabeled samples. Returns the number of pseudo-labeled samples.
+    pub fn fit_semi_supervised(
+        &mut self,
+        labeled: &[(Vec<usize>, usize)],
+        unlabeled: &[Vec<usize>],
+    ) -> usize {
+        labeled
+            .iter()
+            .for_each(|(tokens, label)| self.classifier.fit(tokens, *label));
+
+        let mut remaining: Vec<&[usize]> = unlabeled.iter().map(|x| &x[..]).collect();
+        let mut pseudo_labeled_count = 0;
+
+        for _ in 0..self.max_iterations {
+            let (confident, rest): (Vec<_>, Vec<_>) = remaining
+                .into_iter()
+                .map(|tokens| (tokens, self.confident_label(tokens)))
+                .partition(|(_, label)| label.is_some());
+            remaining = rest.into_iter().map(|(tokens, _)| tokens).collect();
+
+            if confident.is_empty() {
+                break;
+            }
+
+            pseudo_labeled_count += confident.len();
+            confident
+                .into_iter()
+                .for_each(|(tokens, label)| self.classifier.fit(tokens, label.unwrap()));
+        }
+
+        pseudo_labeled_count
+    }
+
+    /// Returns the predicted label for the tokenized text if its probability reaches the threshold.
+    fn confident_label(&self, tokens: &[usize]) -> Option<usize> {
+        let probas = self.classifier.predict_probas(tokens);
+        let label = decide(&probas, None, TieBreak::LowestIndex, |_| 0.0);
+        (probas[label] >= self.threshold).then_some(label)
+    }
+
+    /// Returns the wrapped classifier.
+    pub fn classifier(&self) -> &C {
+        &self.classifier
+    }
+
+    /// Consumes the wrapper and returns the wrapped classifier.
+    pub fn into_inner(self) -> C {
+        self.classifier
+    }
+}
+
+impl<C: NaiveBayesClassifier> NaiveBayesClassifier for SelfTraining<C> {
+    /// Fits the wrapped classifier on the specified tokenized text.
+    fn fit(&mut self, tokens: &[usize], label: usize) {
+        self.classifier.fit(tokens, label)
+    }
+
+    /// Predicts the target label for the tokenized text
+    fn predict(&self, tokens: &[usize]) -> usize {
+        self.classifier.predict(tokens)
+    }
+
+    /// Returns the target label probabilities for the tokenized text
+    fn predict_probas(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.classifier.predict_probas(tokens)
+    }
+
+    /// Returns the target label joint log-likelihoods for the tokenized text
+    fn joint_log_likelihood(&self, tokens: &[usize]) -> Box<[f64]> {
+        self.classifier.joint_log_likelihood(tokens)
+    }
+
+    /// Returns the human-readable names of the target labels of the wrapped classifier, if any.
+    fn class_names(&self) -> Option<&[Box<str>]> {
+        self.classifier.class_names()
+    }
+}
@@ -0,0 +1,187 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    sync::OnceLock,
+};
+
+use crate::tokenizer::Tokenizer;
+
+/// A SymSpell-style spelling corrector mapping misspelled words onto the most frequent word of its
+/// dictionary within a maximal edit distance, so that common typos share the token of the correct
+/// word instead of creating singleton features.
+///
+/// Candidates are found through an index of the words with up to `max_distance` characters deleted,
+/// which is rebuilt on first use after deserialization, and ranked by their optimal string
+/// alignment distance to the misspelled word, then by frequency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpellCorrector {
+    /// Maximal edit distance between a word and its correction.
+    max_distance: usize,
+    /// Frequency of each dictionary word.
+    words: IndexMap<Box<str>, usize>,
+    /// Indices of the dictionary words with up to `max_distance` characters deleted, by deletion.
+    #[serde(skip)]
+    deletes: OnceLock<HashMap<Box<str>, Vec<usize>>>,
+}
+
+impl PartialEq for SpellCorrector {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_distance == other.max_distance && self.words == other.words
+    }
+}
+
+impl SpellCorrector {
+    /// Creates a spelling corrector with an empty dictionary, correcting words within
+    /// `max_distance` edits, typically 1 or 2.
+    pub fn new(max_distance: usize) -> Self {
+        assert!(max_distance > 0, "Maximal edit distance must be positive");
+        Self {
+            max_distance,
+            words: IndexMap::new(),
+            deletes: OnceLock::new(),
+        }
+    }
+
+    /// Creates a spelling corrector whose dictionary holds the single words of the tokenizer
+    /// vocabulary occurring at least `min_count` times in the fitted texts, so that typos map onto
+    /// existing vocabulary entries.
+    pub fn from_tokenizer(tokenizer: &Tokenizer, max_distance: usize, min_count: usize) -> Self {
+        let mut corrector = Self::new(max_distance);
+        for (idx, token) in tokenizer.tokens() {
+            let count = tokenizer.term_frequency(idx).unwrap_or(0);
+            if !tokenizer.is_reserved(idx) && count >= min_count && Self::is_correctable(token) {
+                corrector.add_word(token, count);
+            }
+        }
+        corrector
+    }
+
+    /// Loads words into the dictionary from a frequency file with one `word count` pair per line,
+    /// separated by whitespace, as distributed with SymSpell. Returns the number of loaded words.
+    pub fn load_dictionary(&mut self, file: &mut dyn Read) -> std::io::Result<usize> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)?;
+
+        let mut n_words = 0;
+        for line in buffer.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split_whitespace();
+            let entry = fields
+                .next()
+                .zip(fields.next().and_then(|count| count.parse().ok()));
+            let Some((word, count)) = entry else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Malformed dictionary line: {:?}", line),
+                ));
+            };
+            self.add_word(word, count);
+            n_words += 1;
+        }
+        Ok(n_words)
+    }
+
+    /// Adds occurrences of a word to the dictionary.
+    pub fn add_word(&mut self, word: &str, count: usize) {
+        *self.words.entry(word.into()).or_default() += count;
+        self.deletes = OnceLock::new();
+    }
+
+    /// The number of words of the dictionary.
+    #[inline]
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns the correction of the word, that is the word itself if it is in the dictionary or
+    /// isn't made of letters only, and otherwise the closest and most frequent dictionary word within
+    /// the maximal edit distance and fewer edits than half the word length, if any.
+    pub fn correct<'a>(&'a self, word: &'a str) -> Option<&'a str> {
+        if self.words.contains_key(word) || !Self::is_correctable(word) {
+            return Some(word);
+        }
+
+        let deletes = self.deletes.get_or_init(|| self.index_deletes());
+        let chars: Vec<char> = word.chars().collect();
+        let mut best: Option<(usize, usize, usize)> = None;
+        for candidate in Self::deletions(&chars, self.max_distance) {
+            for &idx in deletes.get(&*candidate).into_iter().flatten() {
+                let (suggestion, &count) = self.words.get_index(idx).unwrap();
+                let suggestion: Vec<char> = suggestion.chars().collect();
+                let distance = osa_distance(&chars, &suggestion);
+                // Short words are only corrected by fewer edits than half their length.
+                if distance > self.max_distance || 2 * distance >= chars.len() {
+                    continue;
+                }
+                // Closest first, then most frequent, then first added.
+                let key = (distance, usize::MAX - count, idx);
+                if best.is_none_or(|best| key < best) {
+                    best = Some(key);
+                }
+            }
+        }
+
+        best.map(|(_, _, idx)| &**self.words.get_index(idx).unwrap().0)
+    }
+
+    /// Returns whether the word is made of letters only, other words being left uncorrected.
+    fn is_correctable(word: &str) -> bool {
+        !word.is_empty() && word.chars().all(char::is_alphabetic)
+    }
+
+    /// Indexes the dictionary words by their deletions.
+    fn index_deletes(&self) -> HashMap<Box<str>, Vec<usize>> {
+        let mut deletes: HashMap<Box<str>, Vec<usize>> = HashMap::new();
+        for (idx, word) in self.words.keys().enumerate() {
+            let chars: Vec<char> = word.chars().collect();
+            for deletion in Self::deletions(&chars, self.max_distance) {
+                deletes.entry(deletion).or_default().push(idx);
+            }
+        }
+        deletes
+    }
+
+    /// Returns the distinct strings obtained by deleting up to `max_distance` characters of the
+    /// word, including the word itself.
+    fn deletions(word: &[char], max_distance: usize) -> HashSet<Box<str>> {
+        let mut deletions = HashSet::from([word.iter().collect::<String>().into()]);
+        let mut frontier = vec![word.to_vec()];
+        for _ in 0..max_distance {
+            let mut next = Vec::new();
+            for chars in frontier.iter().filter(|chars| chars.len() > 1) {
+                for skipped in 0..chars.len() {
+                    let mut deletion = chars.clone();
+                    deletion.remove(skipped);
+                    if deletions.insert(deletion.iter().collect::<String>().into()) {
+                        next.push(deletion);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        deletions
+    }
+}
+
+/// Returns the optimal string alignment distance between two words, that is the number of
+/// insertions, deletions, substitutions and transpositions of adjacent characters turning one into
+/// the other, without editing a substring twice.
+fn osa_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    (0..=a.len()).for_each(|i| rows[i][0] = i);
+    (0..=b.len()).for_each(|j| rows[0][j] = j);
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            rows[i][j] = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    rows[a.len()][b.len()]
+}
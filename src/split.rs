@@ -0,0 +1,64 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// Splits the shuffled indices of the samples of the dataset into disjoint training, validation
+/// and test sets, whose sizes are given by the `(train, validation, test)` fractions summing to 1.
+/// The validation set allows tuning hyperparameters without biasing the final test estimate.
+pub fn train_val_test<T>(
+    dataset: &[T],
+    fractions: (f64, f64, f64),
+    seed: u64,
+) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    let (train, validation, test) = fractions;
+    assert!(
+        train >= 0.0 && validation >= 0.0 && test >= 0.0,
+        "Split fractions must be non-negative"
+    );
+    assert!(
+        (train + validation + test - 1.0).abs() < 1e-9,
+        "Split fractions must sum to 1"
+    );
+
+    let mut indices: Vec<usize> = (0..dataset.len()).collect();
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let n_test = (dataset.len() as f64 * test).round() as usize;
+    let n_validation =
+        ((dataset.len() as f64 * validation).round() as usize).min(dataset.len() - n_test);
+    let test_indices = indices.split_off(dataset.len() - n_test);
+    let validation_indices = indices.split_off(indices.len() - n_validation);
+    (indices, validation_indices, test_indices)
+}
+
+/// Splits the shuffled indices of the samples into disjoint training and test sets, putting the
+/// `test_fraction` of the samples of every label in the test set, so that both sets keep the label
+/// proportions of the dataset whatever its order.
+pub fn stratified_shuffle_split(
+    labels: &[usize],
+    test_fraction: f64,
+    seed: u64,
+) -> (Vec<usize>, Vec<usize>) {
+    assert!(
+        (0.0..=1.0).contains(&test_fraction),
+        "Test fraction must be in the [0, 1] range"
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let n_labels = labels.iter().map(|&label| label + 1).max().unwrap_or(0);
+    let mut by_label = vec![Vec::new(); n_labels];
+    for (idx, &label) in labels.iter().enumerate() {
+        by_label[label].push(idx);
+    }
+
+    let (mut train, mut test) = (Vec::new(), Vec::new());
+    for mut indices in by_label {
+        indices.shuffle(&mut rng);
+        let n_test = (indices.len() as f64 * test_fraction).round() as usize;
+        test.extend(indices.drain(..n_test));
+        train.extend(indices);
+    }
+
+    // Mixes the labels back, so that online classifiers don't see them one after the other.
+    train.shuffle(&mut rng);
+    test.shuffle(&mut rng);
+    (train, test)
+}
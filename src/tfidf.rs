@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::counts::grow_slice;
+use crate::tokenizer::Tokenizer;
+
+/// Transforms tokenized texts into sparse TF-IDF weighted feature vectors, which can be used to
+/// fit and predict with [`crate::bayes::MultinomialNB::fit_features`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TfIdf {
+    /// Number of documents containing each token.
+    document_frequencies: Box<[usize]>,
+    /// Total number of documents.
+    n_documents: usize,
+    /// Whether the feature vectors are normalized to unit euclidean length.
+    normalize: bool,
+}
+
+impl TfIdf {
+    pub fn new(n_features: usize, normalize: bool) -> Self {
+        Self {
+            document_frequencies: vec![0; n_features].into_boxed_slice(),
+            n_documents: 0,
+            normalize,
+        }
+    }
+
+    /// Creates a transformer using the document frequencies tracked by the tokenizer while fitting,
+    /// instead of fitting them again.
+    pub fn from_tokenizer(tokenizer: &Tokenizer, normalize: bool) -> Self {
+        Self {
+            document_frequencies: tokenizer.document_frequencies().into(),
+            n_documents: tokenizer.n_documents(),
+            normalize,
+        }
+    }
+
+    /// Fits the document frequencies on the specified tokenized text.
+    pub fn fit(&mut self, tokens: &[usize]) {
+        let mut unique = tokens.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+
+        if let Some(&max_token) = unique.last() {
+            grow_slice(&mut self.document_frequencies, max_token + 1);
+        }
+
+        for token in unique {
+            self.document_frequencies[token] += 1;
+        }
+
+        self.n_documents += 1;
+    }
+
+    /// Returns the smoothed inverse document frequency of the token, as `ln((1 + n) / (1 + df)) + 1`.
+    pub fn idf(&self, token: usize) -> f64 {
+        let df = self.document_frequencies.get(token).copied().unwrap_or(0);
+        ((1 + self.n_documents) as f64 / (1 + df) as f64).ln() + 1.0
+    }
+
+    /// Transforms the tokenized text into sparse (token, TF-IDF weight) pairs, sorted by token.
+    pub fn transform(&self, tokens: &[usize]) -> Vec<(usize, f64)> {
+        let mut features: Vec<(usize, f64)> = tokens
+            .iter()
+            .copied()
+            .fold(HashMap::new(), |mut map, val| {
+                map.entry(val).and_modify(|frq| *frq += 1usize).or_insert(1);
+                map
+            })
+            .into_iter()
+            .map(|(token, tf)| (token, tf as f64 * self.idf(token)))
+            .collect();
+
+        if self.normalize {
+            let norm = features.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                features.iter_mut().for_each(|(_, w)| *w /= norm);
+            }
+        }
+
+        features.sort_unstable_by_key(|(token, _)| *token);
+        features
+    }
+}
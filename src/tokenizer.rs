@@ -1,23 +1,1028 @@
-use indexmap::IndexSet;
+use fixedbitset::FixedBitSet;
+use indexmap::{IndexMap, IndexSet};
+use rayon::prelude::*;
 use regex::Regex;
+use rust_stemmers::{Algorithm, Stemmer};
 use serde::{de::Error, Deserialize, Serialize};
-use std::{borrow::Cow, io::Read};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Read,
+    ops::Deref,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization as _};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    cache::LruCache,
+    error::RnbError,
+    html::strip_html,
+    preprocess::{Preprocessor, TextPreprocessor},
+    remap::FeatureRemap,
+    spelling::SpellCorrector,
+};
+
+/// Locale whose case folding rules differ from the default Unicode ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CaseLocale {
+    /// Default Unicode case folding.
+    #[default]
+    Default,
+    /// Turkish and Azerbaijani, where `I` folds to the dotless `ı` and `İ` to `i`.
+    Turkic,
+}
+
+impl CaseLocale {
+    /// Folds the case of the text, so that texts differing only by case compare equal.
+    pub(crate) fn fold_case<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if text
+            .bytes()
+            .all(|b| b.is_ascii() && !b.is_ascii_uppercase())
+        {
+            return Cow::Borrowed(text);
+        }
+
+        Cow::Owned(match self {
+            CaseLocale::Default => caseless::default_case_fold_str(text),
+            CaseLocale::Turkic => {
+                let dotless: String = text
+                    .chars()
+                    .map(|c| match c {
+                        'I' => 'ı',
+                        'İ' => 'i',
+                        c => c,
+                    })
+                    .collect();
+                caseless::default_case_fold_str(&dotless)
+            }
+        })
+    }
+}
+
+/// Unicode normalization applied to the text before any other processing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum UnicodeNormalization {
+    /// The text is left as is.
+    #[default]
+    None,
+    /// NFKC normalization, unifying compatibility characters such as full-width letters or
+    /// ligatures with their usual form.
+    Nfkc,
+    /// NFKC normalization and removal of diacritics, e.g. "café" becomes "cafe".
+    NfkcStripAccents,
+}
+
+impl UnicodeNormalization {
+    /// Normalizes the text.
+    pub(crate) fn apply<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match self {
+            UnicodeNormalization::None => Cow::Borrowed(text),
+            UnicodeNormalization::Nfkc => Cow::Owned(text.nfkc().collect()),
+            UnicodeNormalization::NfkcStripAccents => Cow::Owned(
+                text.nfkd()
+                    .filter(|&c| !is_combining_mark(c))
+                    .nfkc()
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A kind of text span replaced with a placeholder token before the text is split into words, so
+/// that e.g. every URL shares a single token instead of being shredded into noise tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Placeholder {
+    /// Email addresses, replaced with [`Tokenizer::EMAIL_TOKEN`].
+    Email,
+    /// URLs starting with a scheme or `www.`, excluding trailing punctuation, replaced with
+    /// [`Tokenizer::URL_TOKEN`].
+    Url,
+    /// Phone numbers, replaced with [`Tokenizer::PHONE_TOKEN`].
+    PhoneNumber,
+    /// Numbers, replaced with [`Tokenizer::NUM_TOKEN`].
+    Number,
+}
+
+impl Placeholder {
+    /// Returns the pattern matching the spans to replace.
+    fn pattern(&self) -> &'static str {
+        match self {
+            Placeholder::Email => r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+",
+            Placeholder::Url => r#"(?i)\b(?:[a-z][a-z0-9+.-]*://|www\.)\S*[^\s.,!?;:'")\]]"#,
+            Placeholder::PhoneNumber => r"\+?\d[\d \t().-]{6,}\d",
+            Placeholder::Number => r"\d+(?:[.,]\d+)*",
+        }
+    }
+
+    /// Returns the placeholder token the spans are replaced with.
+    fn token(&self) -> &'static str {
+        match self {
+            Placeholder::Email => Tokenizer::EMAIL_TOKEN,
+            Placeholder::Url => Tokenizer::URL_TOKEN,
+            Placeholder::PhoneNumber => Tokenizer::PHONE_TOKEN,
+            Placeholder::Number => Tokenizer::NUM_TOKEN,
+        }
+    }
+
+    /// Replaces the spans of the text with the placeholder token.
+    pub(crate) fn replace<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        static REGEXES: LazyLock<[Regex; 4]> = LazyLock::new(|| {
+            [
+                Placeholder::Email,
+                Placeholder::Url,
+                Placeholder::PhoneNumber,
+                Placeholder::Number,
+            ]
+            .map(|placeholder| Regex::new(placeholder.pattern()).unwrap())
+        });
+
+        let replacement = format!(" {} ", self.token());
+        REGEXES[*self as usize].replace_all(text, replacement.as_str())
+    }
+}
+
+/// How emoji are tokenized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum EmojiMode {
+    /// Emoji are left as is, glued to the adjacent characters.
+    #[default]
+    Keep,
+    /// Each emoji, including its skin tone and ZWJ sequence, is split into a standalone token.
+    Split,
+    /// Each emoji is split into a standalone token holding its description without skin tone,
+    /// e.g. "😂" becomes `<face_with_tears_of_joy>`.
+    Describe,
+}
+
+impl EmojiMode {
+    /// Pattern matching emoji, their variation selector, skin tone and ZWJ sequences, and flags.
+    const PATTERN: &'static str = concat!(
+        r"\p{Extended_Pictographic}[\x{FE0F}\p{Emoji_Modifier}]*",
+        r"(?:\x{200D}\p{Extended_Pictographic}[\x{FE0F}\p{Emoji_Modifier}]*)*",
+        r"|\p{Regional_Indicator}{2}",
+    );
+
+    /// Splits the emoji of the text into standalone tokens.
+    pub(crate) fn apply<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(EmojiMode::PATTERN).unwrap());
+
+        match self {
+            EmojiMode::Keep => Cow::Borrowed(text),
+            EmojiMode::Split => REGEX.replace_all(text, " $0 "),
+            EmojiMode::Describe => {
+                REGEX.replace_all(text, |caps: &regex::Captures| match emojis::get(&caps[0]) {
+                    Some(emoji) => format!(" {} ", Self::describe(emoji)),
+                    None => format!(" {} ", &caps[0]),
+                })
+            }
+        }
+    }
+
+    /// Returns whether the token is the description token of an emoji.
+    fn is_description(token: &str) -> bool {
+        static DESCRIPTIONS: LazyLock<HashSet<String>> =
+            LazyLock::new(|| emojis::iter().map(EmojiMode::describe).collect());
+        DESCRIPTIONS.contains(token)
+    }
+
+    /// Returns the description token of the emoji, ignoring its skin tone.
+    fn describe(emoji: &emojis::Emoji) -> String {
+        let emoji = emoji
+            .with_skin_tone(emojis::SkinTone::Default)
+            .unwrap_or(emoji);
+        let name = emoji
+            .name()
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("_");
+        format!("<{name}>")
+    }
+}
+
+/// How the normalized text is split into words.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Segmentation {
+    /// Punctuation is spaced out and the text split on whitespace.
+    #[default]
+    Whitespace,
+    /// Words are the matches of a regex, like sklearn's `\b\w\w+\b`, and punctuation isn't spaced
+    /// out. Placeholder and emoji description tokens are only kept if the pattern matches them,
+    /// e.g. with `<\w+>|\b\w\w+\b`.
+    TokenPattern(Box<str>),
+    /// Words are split on Unicode word boundaries (UAX #29), keeping punctuation as standalone
+    /// words, and punctuation isn't spaced out. Works across scripts without a punctuation regex.
+    UnicodeWords,
+}
+
+/// A segmentation whose token pattern is compiled, serialized as the [`Segmentation`] it was
+/// compiled from. Deserialization fails if the token pattern is invalid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Segmentation", into = "Segmentation")]
+enum Segmenter {
+    Whitespace,
+    TokenPattern(Regex),
+    UnicodeWords,
+}
+
+impl TryFrom<Segmentation> for Segmenter {
+    type Error = regex::Error;
+
+    fn try_from(segmentation: Segmentation) -> Result<Self, Self::Error> {
+        Ok(match segmentation {
+            Segmentation::Whitespace => Segmenter::Whitespace,
+            Segmentation::TokenPattern(pattern) => Segmenter::TokenPattern(Regex::new(&pattern)?),
+            Segmentation::UnicodeWords => Segmenter::UnicodeWords,
+        })
+    }
+}
+
+impl From<Segmenter> for Segmentation {
+    fn from(segmenter: Segmenter) -> Self {
+        match segmenter {
+            Segmenter::Whitespace => Segmentation::Whitespace,
+            Segmenter::TokenPattern(regex) => Segmentation::TokenPattern(regex.as_str().into()),
+            Segmenter::UnicodeWords => Segmentation::UnicodeWords,
+        }
+    }
+}
+
+impl Segmenter {
+    /// Splits the preprocessed text into words. Whitespace segmentation splits out the `punct`
+    /// matches on the fly. Whitespace and Unicode word segmentations keep whole the
+    /// whitespace-separated tokens for which `keep_whole` holds, which all start with `<`.
+    fn segment<'t>(
+        &self,
+        text: &'t str,
+        punct: &Regex,
+        keep_whole: impl Fn(&str) -> bool,
+    ) -> Vec<&'t str> {
+        match self {
+            Segmenter::Whitespace => {
+                let mut words = Vec::new();
+                let mut last = 0;
+                for (at, _) in text.match_indices('<') {
+                    if !text[..at]
+                        .chars()
+                        .next_back()
+                        .is_none_or(char::is_whitespace)
+                    {
+                        continue;
+                    }
+                    let end = text[at..]
+                        .find(char::is_whitespace)
+                        .map_or(text.len(), |len| at + len);
+                    if keep_whole(&text[at..end]) {
+                        split_punct(&text[last..at], punct, &mut words);
+                        words.push(&text[at..end]);
+                        last = end;
+                    }
+                }
+                split_punct(&text[last..], punct, &mut words);
+                words
+            }
+            Segmenter::TokenPattern(regex) => regex.find_iter(text).map(|m| m.as_str()).collect(),
+            Segmenter::UnicodeWords => text
+                .split_whitespace()
+                .flat_map(|chunk| {
+                    if keep_whole(chunk) {
+                        vec![chunk]
+                    } else {
+                        chunk.split_word_bounds().collect()
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Splits the text into words as if every `punct` match were replaced with its first capture
+/// group surrounded by spaces before splitting on whitespace, without building that string.
+fn split_punct<'t>(text: &'t str, punct: &Regex, words: &mut Vec<&'t str>) {
+    let mut locations = punct.capture_locations();
+    let (mut last, mut at, mut last_end) = (0, 0, None);
+    while let Some(matched) = punct.captures_read_at(&mut locations, text, at) {
+        // Like `Regex::replace_all`, skips the empty matches at the end of the previous match.
+        if matched.is_empty() && last_end == Some(matched.end()) {
+            match text[matched.end()..].chars().next() {
+                Some(c) => at = matched.end() + c.len_utf8(),
+                None => break,
+            }
+            continue;
+        }
+        words.extend(text[last..matched.start()].split_whitespace());
+        if let Some((start, end)) = locations.get(1) {
+            words.extend(text[start..end].split_whitespace());
+        }
+        (last, at, last_end) = (matched.end(), matched.end(), Some(matched.end()));
+    }
+    words.extend(text[last..].split_whitespace());
+}
+
+/// How words written in Chinese or Japanese scripts, which don't separate words with spaces, are
+/// split into tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CjkMode {
+    /// Words are left as is, so that a whole sentence may be a single word.
+    #[default]
+    Keep,
+    /// Each CJK character is a word.
+    Chars,
+    /// Each pair of adjacent CJK characters is a word, and isolated characters are words.
+    Bigrams,
+}
+
+impl CjkMode {
+    /// Splits the runs of CJK characters of the words.
+    fn split<'t>(&self, words: Vec<&'t str>) -> Vec<&'t str> {
+        if *self == CjkMode::Keep || !words.iter().any(|word| word.chars().any(is_cjk)) {
+            return words;
+        }
+
+        let mut split = Vec::with_capacity(words.len());
+        for word in words {
+            let mut rest = word;
+            while let Some(c) = rest.chars().next() {
+                let cjk = is_cjk(c);
+                let run_len = rest.find(|c| is_cjk(c) != cjk).unwrap_or(rest.len());
+                let (run, tail) = rest.split_at(run_len);
+                rest = tail;
+
+                if !cjk {
+                    split.push(run);
+                    continue;
+                }
+                let bounds: Vec<usize> = run
+                    .char_indices()
+                    .map(|(idx, _)| idx)
+                    .chain([run.len()])
+                    .collect();
+                match self {
+                    CjkMode::Bigrams if bounds.len() > 2 => {
+                        split.extend(bounds.windows(3).map(|b| &run[b[0]..b[2]]))
+                    }
+                    _ => split.extend(bounds.windows(2).map(|b| &run[b[0]..b[1]])),
+                }
+            }
+        }
+        split
+    }
+}
+
+/// Returns whether the character belongs to the Han, Hiragana or Katakana scripts.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}'
+        | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Which terms of the text make up the vocabulary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TokenMode {
+    /// Word n-grams.
+    #[default]
+    Words,
+    /// Character n-grams of each word padded with spaces, robust to misspellings and obfuscation
+    /// like "v1agra".
+    CharNgrams,
+    /// Both word n-grams and character n-grams. Character n-grams equal to a word share its token.
+    WordsAndCharNgrams,
+}
+
+/// Counts of the words and adjacent word pairs of the fitted texts, used to detect phrases.
+#[derive(Debug, Default)]
+struct WordStats {
+    /// Number of occurrences of each word.
+    word_counts: HashMap<Box<str>, usize>,
+    /// Number of occurrences of each pair of adjacent words.
+    pair_counts: HashMap<(Box<str>, Box<str>), usize>,
+}
+
+impl WordStats {
+    /// Counts the words and adjacent word pairs of a text.
+    fn record(&mut self, words: &[Box<str>]) {
+        for word in words {
+            *self.word_counts.entry(word.clone()).or_default() += 1;
+        }
+        for pair in words.windows(2) {
+            *self
+                .pair_counts
+                .entry((pair[0].clone(), pair[1].clone()))
+                .or_default() += 1;
+        }
+    }
+}
+
+/// A word or term of a text, borrowed from the text or the tokenizer, shared with the cache of
+/// normalized words, or built from the text.
+#[derive(Debug, Clone)]
+enum Term<'a> {
+    Borrowed(&'a str),
+    Shared(Arc<str>),
+    Owned(String),
+}
+
+impl Deref for Term<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Term::Borrowed(term) => term,
+            Term::Shared(term) => term,
+            Term::Owned(term) => term,
+        }
+    }
+}
+
+impl Borrow<str> for Term<'_> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Display for Term<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Term<'a> {
+    fn from(term: Cow<'a, str>) -> Self {
+        match term {
+            Cow::Borrowed(term) => Term::Borrowed(term),
+            Cow::Owned(term) => Term::Owned(term),
+        }
+    }
+}
+
+impl From<Term<'_>> for Box<str> {
+    fn from(term: Term<'_>) -> Self {
+        match term {
+            Term::Owned(term) => term.into(),
+            term => (*term).into(),
+        }
+    }
+}
 
 /// A text tokenizer
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Tokenizer {
     /// The tokenizer vocabulary dictionnary.
     dict: IndexSet<Box<str>>,
-    /// The set of punctuation characters to normalize.
-    punct: Box<str>,
+    /// The regex matching the punctuation characters to normalize.
+    #[serde(with = "regex_serde")]
+    punct: Regex,
+    /// Preprocessors applied in order to the text before the built-in processing stages.
+    preprocessors: Vec<Preprocessor>,
+    /// User-defined preprocessors applied in order after the serializable ones.
+    #[serde(skip)]
+    custom_preprocessors: Vec<Box<dyn TextPreprocessor + Send + Sync>>,
+    /// Whether HTML tags are stripped and character references decoded before any other built-in
+    /// processing.
+    strip_html: bool,
+    /// Unicode normalization applied to the text before any other processing but HTML stripping.
+    unicode_normalization: UnicodeNormalization,
+    /// Kinds of text spans replaced with a placeholder token, in replacement order.
+    placeholders: Vec<Placeholder>,
+    /// Whether runs of a repeated character are collapsed.
+    collapse_repeats: bool,
+    /// How emoji are tokenized.
+    emoji_mode: EmojiMode,
+    /// How the normalized text is split into words.
+    segmentation: Segmenter,
+    /// How words written in Chinese or Japanese scripts are split into tokens.
+    cjk_mode: CjkMode,
+    /// Whether the text is case folded before being split into words.
+    lowercase: bool,
+    /// The locale whose case folding rules are used.
+    case_locale: CaseLocale,
+    /// Words dropped from the text before tokenization.
+    stopwords: IndexSet<Box<str>>,
+    /// Minimal number of characters of the words kept in the text.
+    min_token_len: usize,
+    /// Corrector mapping misspelled words onto dictionary words, if any.
+    spell_corrector: Option<SpellCorrector>,
+    /// Whether words are reduced to their English stem.
+    stem: bool,
+    /// Lemma of each known inflected word form.
+    lemmas: IndexMap<Box<str>, Box<str>>,
+    /// Number of words following a negation word which are negated, negation being disabled at 0.
+    negation_window: usize,
+    /// Words negating the words following them.
+    negation_words: IndexSet<Box<str>>,
+    /// The minimal and maximal number of consecutive words making up a token.
+    ngram_range: (usize, usize),
+    /// Which terms of the text make up the vocabulary.
+    token_mode: TokenMode,
+    /// The minimal and maximal number of characters making up a character n-gram.
+    char_ngram_range: (usize, usize),
+    /// The maximal distance between the two words of a skip-gram, skip-grams being disabled below 2.
+    skipgram_window: usize,
+    /// Number of occurrences of each token in the fitted texts.
+    term_frequencies: Vec<usize>,
+    /// Number of fitted texts containing each token.
+    document_frequencies: Vec<usize>,
+    /// Number of fitted texts.
+    n_documents: usize,
+    /// Maximal number of tokens in the vocabulary, if any.
+    max_vocab: Option<usize>,
+    /// Number of special tokens, which are the first tokens of the vocabulary.
+    n_special: usize,
+    /// Index of the token unknown words are mapped to, if any.
+    unk: Option<usize>,
+    /// Whether fitting new texts is forbidden.
+    frozen: bool,
+    /// Pairs of adjacent words merged into a single word, indexed by their first word.
+    phrases: IndexMap<Box<str>, IndexSet<Box<str>>>,
+    /// Whether word statistics are collected while fitting, to detect phrases.
+    phrase_detection: bool,
+    /// Word statistics of the texts fitted since the last phrase detection.
+    #[serde(skip)]
+    word_stats: WordStats,
+    /// Normalized form of the recently seen words, if it differs from the word, used when spell
+    /// correction or stemming is enabled. Words are spread over independently locked shards.
+    #[serde(skip, default = "word_cache")]
+    word_cache: Box<[WordCacheShard]>,
+}
+
+/// Maximal number of words whose normalized form is cached by a tokenizer.
+const WORD_CACHE_CAPACITY: usize = 1 << 16;
+
+/// Number of shards of the cache of normalized words, so that texts tokenized in parallel seldom
+/// wait for each other.
+const WORD_CACHE_SHARDS: usize = 16;
+
+/// A shard of the cache of normalized words, holding `None` for the words left unchanged.
+type WordCacheShard = Mutex<LruCache<Option<Arc<str>>>>;
+
+/// Creates an empty cache of normalized words.
+fn word_cache() -> Box<[WordCacheShard]> {
+    (0..WORD_CACHE_SHARDS)
+        .map(|_| Mutex::new(LruCache::new(WORD_CACHE_CAPACITY / WORD_CACHE_SHARDS)))
+        .collect()
 }
 
 impl Tokenizer {
-    pub fn new(punct: &str) -> Self {
-        Self {
+    /// Text of the token unknown words are mapped to.
+    pub const UNK_TOKEN: &'static str = "<unk>";
+    /// Text of the padding token.
+    pub const PAD_TOKEN: &'static str = "<pad>";
+    /// Text of the special token standing for phone numbers.
+    pub const PHONE_TOKEN: &'static str = "<phone>";
+    /// Text of the special token standing for numbers.
+    pub const NUM_TOKEN: &'static str = "<num>";
+    /// Text of the special token standing for URLs.
+    pub const URL_TOKEN: &'static str = "<url>";
+    /// Text of the special token standing for email addresses.
+    pub const EMAIL_TOKEN: &'static str = "<email>";
+    /// Common English negation words, to be passed to [`Tokenizer::set_negation`].
+    pub const NEGATION_WORDS: &'static [&'static str] = &[
+        "not",
+        "no",
+        "never",
+        "nor",
+        "none",
+        "nothing",
+        "nobody",
+        "nowhere",
+        "neither",
+        "cannot",
+        "without",
+        "isn't",
+        "aren't",
+        "wasn't",
+        "weren't",
+        "don't",
+        "doesn't",
+        "didn't",
+        "won't",
+        "wouldn't",
+        "can't",
+        "couldn't",
+        "shouldn't",
+        "haven't",
+        "hasn't",
+        "hadn't",
+    ];
+
+    /// Creates a tokenizer with the regex matching the punctuation characters to normalize, which
+    /// must have a capture group. Returns an error if the regex is invalid.
+    pub fn new(punct: &str) -> Result<Self, RnbError> {
+        Ok(Self {
             dict: IndexSet::new(),
-            punct: punct.into(),
+            punct: Regex::new(punct)?,
+            preprocessors: Vec::new(),
+            custom_preprocessors: Vec::new(),
+            strip_html: false,
+            unicode_normalization: UnicodeNormalization::default(),
+            placeholders: Vec::new(),
+            collapse_repeats: false,
+            emoji_mode: EmojiMode::default(),
+            segmentation: Segmenter::Whitespace,
+            cjk_mode: CjkMode::default(),
+            lowercase: false,
+            case_locale: CaseLocale::default(),
+            stopwords: IndexSet::new(),
+            min_token_len: 0,
+            spell_corrector: None,
+            stem: false,
+            lemmas: IndexMap::new(),
+            negation_window: 0,
+            negation_words: IndexSet::new(),
+            ngram_range: (1, 1),
+            token_mode: TokenMode::default(),
+            char_ngram_range: (3, 5),
+            skipgram_window: 0,
+            term_frequencies: Vec::new(),
+            document_frequencies: Vec::new(),
+            n_documents: 0,
+            max_vocab: None,
+            n_special: 0,
+            unk: None,
+            frozen: false,
+            phrases: IndexMap::new(),
+            phrase_detection: false,
+            word_stats: WordStats::default(),
+            word_cache: word_cache(),
+        })
+    }
+
+    /// Sets the chain of preprocessors applied in order to the text before the built-in processing
+    /// stages, e.g. custom regex rewriting rules. Should be set before fitting.
+    pub fn set_preprocessors(&mut self, preprocessors: impl IntoIterator<Item = Preprocessor>) {
+        self.preprocessors = preprocessors.into_iter().collect();
+    }
+
+    /// Appends a user-defined preprocessor to the chain, applied after the preprocessors set by
+    /// [`Self::set_preprocessors`]. Custom preprocessors are neither saved with the tokenizer nor
+    /// part of its [`Self::config`], so they must be added again to loaded or rebuilt tokenizers.
+    /// Should be set before fitting.
+    pub fn add_preprocessor(
+        &mut self,
+        preprocessor: impl TextPreprocessor + Send + Sync + 'static,
+    ) {
+        self.custom_preprocessors.push(Box::new(preprocessor));
+    }
+
+    /// Sets whether HTML tags, comments, scripts and styles are stripped and character references
+    /// such as `&amp;` decoded before any other built-in processing. Should be set before fitting.
+    pub fn set_strip_html(&mut self, strip_html: bool) {
+        self.strip_html = strip_html;
+    }
+
+    /// Sets the Unicode normalization applied to the text before any other processing but HTML
+    /// stripping. Should be set before fitting.
+    pub fn set_unicode_normalization(&mut self, unicode_normalization: UnicodeNormalization) {
+        self.unicode_normalization = unicode_normalization;
+    }
+
+    /// Sets the kinds of text spans replaced with a placeholder token before splitting the text into
+    /// words. Replacements are applied in the [`Placeholder`] declaration order, e.g. so that email
+    /// addresses aren't matched as URLs. The punctuation pattern shouldn't split the `<` and `>`
+    /// characters of the placeholder tokens. Should be set before fitting.
+    pub fn set_placeholders(&mut self, placeholders: impl IntoIterator<Item = Placeholder>) {
+        self.placeholders = placeholders.into_iter().collect();
+        self.placeholders.sort_unstable();
+        self.placeholders.dedup();
+    }
+
+    /// Sets whether runs of a repeated character are collapsed, so that stylistic lengthening
+    /// doesn't fragment the vocabulary: letters are kept at most twice ("soooo" becomes "soo") and
+    /// punctuation and symbols once ("!!!!" becomes "!"). Digits are left as is.
+    /// Should be set before fitting.
+    pub fn set_collapse_repeats(&mut self, collapse_repeats: bool) {
+        self.collapse_repeats = collapse_repeats;
+    }
+
+    /// Sets how emoji are tokenized. Emoji are strong sentiment and spam signals, which splitting
+    /// them from the adjacent words exposes. Should be set before fitting.
+    pub fn set_emoji_mode(&mut self, emoji_mode: EmojiMode) {
+        self.emoji_mode = emoji_mode;
+    }
+
+    /// Sets how the normalized text is split into words. Returns an error if the token pattern is
+    /// invalid. Should be set before fitting.
+    pub fn set_segmentation(&mut self, segmentation: Segmentation) -> Result<(), RnbError> {
+        self.segmentation = Segmenter::try_from(segmentation)?;
+        Ok(())
+    }
+
+    /// Sets how words written in Chinese or Japanese scripts are split into tokens, since whitespace
+    /// segmentation makes whole sentences of these scripts single words. Should be set before
+    /// fitting.
+    pub fn set_cjk_mode(&mut self, cjk_mode: CjkMode) {
+        self.cjk_mode = cjk_mode;
+    }
+
+    /// Sets whether the text is lowercased before being split into words, so that words differing
+    /// only by case share a token. Uses full Unicode case folding, e.g. `ß` folds to `ss`.
+    /// Should be set before fitting.
+    pub fn set_lowercase(&mut self, lowercase: bool) {
+        self.lowercase = lowercase;
+    }
+
+    /// Sets the locale whose case folding rules are used when lowercasing.
+    /// Should be set before fitting.
+    pub fn set_case_locale(&mut self, case_locale: CaseLocale) {
+        self.case_locale = case_locale;
+    }
+
+    /// Sets the words dropped from the text before tokenization, e.g. [`crate::stopwords::ENGLISH`]
+    /// chained with domain-specific words. Stop words are compared to the normalized words, so should
+    /// be lowercase if lowercasing is enabled. Should be set before fitting.
+    pub fn set_stopwords<'a>(&mut self, stopwords: impl IntoIterator<Item = &'a str>) {
+        self.stopwords = stopwords.into_iter().map(|word| word.into()).collect();
+    }
+
+    /// Sets the minimal number of characters of the words kept in the text, dropping fragments like
+    /// the stray "s" and "t" of split contractions. Words are measured before stemming, and
+    /// punctuation split into standalone words is dropped too. Should be set before fitting.
+    pub fn set_min_token_len(&mut self, min_token_len: usize) {
+        self.min_token_len = min_token_len;
+    }
+
+    /// Sets the corrector mapping misspelled words onto the words of its dictionary, e.g. built
+    /// from a first fitting pass with [`SpellCorrector::from_tokenizer`]. Words are corrected after
+    /// stop words are removed and before lemmatization. Should be set before fitting.
+    pub fn set_spell_corrector(&mut self, spell_corrector: Option<SpellCorrector>) {
+        self.spell_corrector = spell_corrector;
+        self.clear_word_cache();
+    }
+
+    /// Sets whether words are reduced to their stem using the Snowball English (Porter2) stemmer, so
+    /// that e.g. "winning" and "wins" share a token. Stop words are removed before stemming.
+    /// Should be set before fitting.
+    pub fn set_stem(&mut self, stem: bool) {
+        self.stem = stem;
+        self.clear_word_cache();
+    }
+
+    /// Sets the lemma of each inflected word form, so that e.g. "mice" and "mouse" share a token.
+    /// Words are lemmatized after stop word removal and before stemming.
+    /// Should be set before fitting.
+    pub fn set_lemmas<'a>(&mut self, lemmas: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        self.lemmas = lemmas
+            .into_iter()
+            .map(|(form, lemma)| (form.into(), lemma.into()))
+            .collect();
+        self.clear_word_cache();
+    }
+
+    /// Loads the lemmas from a TSV file with one `form<TAB>lemma` pair per line, replacing the
+    /// current ones as [`Self::set_lemmas`]. Returns the number of loaded lemmas.
+    pub fn load_lemmas(&mut self, file: &mut dyn Read) -> std::io::Result<usize> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)?;
+
+        let lemmas = buffer
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_once('\t').ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Malformed lemma line: {:?}", line),
+                    )
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        self.set_lemmas(lemmas);
+        Ok(self.lemmas.len())
+    }
+
+    /// Sets the number of words following a negation word, e.g. from [`Tokenizer::NEGATION_WORDS`],
+    /// which are rewritten as `not_<word>` ("not good" becomes "not not_good"). Negation stops early
+    /// at clause-ending punctuation. Negation words are compared to the normalized words before stop
+    /// words are removed, and a window of 0 disables negation. Should be set before fitting.
+    pub fn set_negation<'a>(&mut self, window: usize, words: impl IntoIterator<Item = &'a str>) {
+        self.negation_window = window;
+        self.negation_words = words.into_iter().map(|word| word.into()).collect();
+    }
+
+    /// Sets the minimal and maximal number of consecutive words making up a token, e.g. `(1, 2)` to
+    /// use both single words and pairs of words like "click here" as tokens.
+    /// Should be set before fitting.
+    pub fn set_ngram_range(&mut self, min_n: usize, max_n: usize) {
+        assert!(
+            min_n > 0 && min_n <= max_n,
+            "N-gram range must be non-empty and start at 1 or more"
+        );
+        self.ngram_range = (min_n, max_n);
+    }
+
+    /// Sets which terms of the text make up the vocabulary. Should be set before fitting.
+    pub fn set_token_mode(&mut self, token_mode: TokenMode) {
+        self.token_mode = token_mode;
+    }
+
+    /// Sets the minimal and maximal number of characters making up a character n-gram, including
+    /// the padding space on each side of the word. Should be set before fitting.
+    pub fn set_char_ngram_range(&mut self, min_n: usize, max_n: usize) {
+        assert!(
+            min_n > 0 && min_n <= max_n,
+            "N-gram range must be non-empty and start at 1 or more"
+        );
+        self.char_ngram_range = (min_n, max_n);
+    }
+
+    /// Sets the maximal distance between the two words of a skip-gram. Skip-grams are pairs of
+    /// non-adjacent words at most `window` words apart, written as "first … second", capturing word
+    /// combinations without the vocabulary growth of long n-grams. Disabled when `window` is below 2.
+    /// Should be set before fitting.
+    pub fn set_skipgram_window(&mut self, window: usize) {
+        self.skipgram_window = window;
+    }
+
+    /// Sets whether the words and adjacent word pairs of the fitted texts are counted, so that
+    /// phrases can be detected with [`Self::detect_phrases`]. Counting is disabled by default, as
+    /// its memory usage grows with the fitted texts. Disabling it discards the current counts.
+    pub fn set_phrase_detection(&mut self, phrase_detection: bool) {
+        self.phrase_detection = phrase_detection;
+        if !phrase_detection {
+            self.word_stats = WordStats::default();
+        }
+    }
+
+    /// Detects phrases in the texts fitted since the last detection, merging the pairs of adjacent
+    /// words which occur together more often than by chance into a single word, e.g. "new_york".
+    ///
+    /// Pairs occurring at least `min_count` times are scored as
+    /// `(pair_count - min_count) * word_count / (first_count * second_count)`, where `word_count` is
+    /// the number of distinct words, and kept if their score exceeds the threshold. Only texts fitted
+    /// with phrase detection enabled by [`Self::set_phrase_detection`] are taken into account.
+    ///
+    /// Clears the vocabulary, which must be rebuilt by fitting the texts again so that it contains
+    /// the phrases. Detecting phrases again afterwards allows building longer phrases.
+    /// Returns the number of newly detected phrases, or fails if the vocabulary is frozen.
+    pub fn detect_phrases(&mut self, min_count: usize, threshold: f64) -> Result<usize, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
         }
+
+        let stats = std::mem::take(&mut self.word_stats);
+        let distinct_words = stats.word_counts.len() as f64;
+        let mut detected = 0;
+
+        for ((first, second), &count) in stats.pair_counts.iter() {
+            if count < min_count {
+                continue;
+            }
+
+            let score = (count - min_count) as f64 * distinct_words
+                / (stats.word_counts[first] * stats.word_counts[second]) as f64;
+            if score > threshold
+                && self
+                    .phrases
+                    .entry(first.clone())
+                    .or_default()
+                    .insert(second.clone())
+            {
+                detected += 1;
+            }
+        }
+
+        self.dict.truncate(self.n_special);
+        self.term_frequencies = vec![0; self.n_special];
+        self.document_frequencies = vec![0; self.n_special];
+        self.n_documents = 0;
+        if self.unk.is_some_and(|unk| unk >= self.n_special) {
+            self.unk = None;
+            self.insert_unk();
+        }
+        Ok(detected)
+    }
+
+    /// Sets the maximal number of tokens in the vocabulary, keeping only the most frequent ones.
+    ///
+    /// To bound memory usage, the vocabulary is pruned back to the cap whenever it grows to twice
+    /// the cap while fitting, which reindexes the tokens: tokens returned by previous calls to
+    /// [`Self::fit`] may become invalid, so texts should be tokenized once fitting is done, after
+    /// calling [`Self::limit_vocab`].
+    pub fn set_max_vocab(&mut self, max_vocab: Option<usize>) {
+        assert!(max_vocab != Some(0), "Vocabulary cap must be non-zero");
+        self.max_vocab = max_vocab;
+    }
+
+    /// Keeps only the most frequent tokens if the vocabulary exceeds its cap, and reindexes the
+    /// remaining ones. Returns the remapping, which should also be applied to classifiers trained
+    /// with the tokenizer, or fails if the vocabulary is frozen.
+    pub fn limit_vocab(&mut self) -> Result<FeatureRemap, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+        Ok(self.cap_vocab())
+    }
+
+    /// Prunes the vocabulary back to its cap, as [`Self::limit_vocab`].
+    fn cap_vocab(&mut self) -> FeatureRemap {
+        let remap = match self.max_vocab {
+            Some(max_vocab) if self.dict.len() > max_vocab => {
+                let mut frequencies: Vec<f64> =
+                    self.term_frequencies.iter().map(|&f| f as f64).collect();
+                frequencies
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(idx, _)| self.is_reserved(*idx))
+                    .for_each(|(_, frequency)| *frequency = f64::INFINITY);
+                FeatureRemap::top_k(&frequencies, max_vocab)
+            }
+            _ => FeatureRemap::new(self.dict.len(), |_| true),
+        };
+        self.remap_vocab(&remap);
+        remap
+    }
+
+    /// Reserves a token which words unknown to the vocabulary are mapped to when tokenizing, instead
+    /// of being dropped. Returns its index. Pruning the vocabulary keeps this token.
+    ///
+    /// Classifiers score this token as a token never seen once given its index, e.g. with
+    /// [`crate::bayes::MultinomialNB::set_unk_token`].
+    ///
+    /// Fails if the vocabulary is frozen.
+    pub fn reserve_unk(&mut self) -> Result<usize, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+        Ok(self.insert_unk())
+    }
+
+    /// Reserves the unknown word token, as [`Self::reserve_unk`].
+    fn insert_unk(&mut self) -> usize {
+        if let Some(unk) = self.unk {
+            return unk;
+        }
+        if self.dict.len() == self.n_special {
+            return self.insert_special_token(Self::UNK_TOKEN);
+        }
+
+        let (unk, inserted) = self.dict.insert_full(Self::UNK_TOKEN.into());
+        if inserted {
+            self.term_frequencies.push(0);
+            self.document_frequencies.push(0);
+        }
+        self.unk = Some(unk);
+        unk
+    }
+
+    /// Returns the index of the token unknown words are mapped to, if reserved.
+    #[inline]
+    pub fn unk_token(&self) -> Option<usize> {
+        self.unk
+    }
+
+    /// Registers a special token, e.g. [`Self::PAD_TOKEN`], at the next fixed index of the
+    /// vocabulary and returns its index. Special tokens are never pruned and keep the same indices
+    /// across retrains as long as they are registered in the same order. Registering
+    /// [`Self::UNK_TOKEN`] maps unknown words to it, as [`Self::reserve_unk`].
+    ///
+    /// Special tokens must be registered before fitting. Fails if the vocabulary is frozen.
+    pub fn register_special_token(&mut self, token: &str) -> Result<usize, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+        Ok(self.insert_special_token(token))
+    }
+
+    /// Registers a special token, as [`Self::register_special_token`].
+    fn insert_special_token(&mut self, token: &str) -> usize {
+        assert_eq!(
+            self.dict.len(),
+            self.n_special,
+            "Special tokens must be registered before fitting"
+        );
+
+        let (idx, inserted) = self.dict.insert_full(token.into());
+        if inserted {
+            self.term_frequencies.push(0);
+            self.document_frequencies.push(0);
+            self.n_special += 1;
+        }
+        if token == Self::UNK_TOKEN {
+            self.unk = Some(idx);
+        }
+        idx
+    }
+
+    /// Returns the registered special tokens, in index order.
+    pub fn special_tokens(&self) -> impl Iterator<Item = &str> {
+        self.dict.iter().take(self.n_special).map(|token| &**token)
+    }
+
+    /// Returns whether the token is a special token or the unknown word token, which are never
+    /// pruned from the vocabulary.
+    #[inline]
+    pub fn is_reserved(&self, idx: usize) -> bool {
+        idx < self.n_special || Some(idx) == self.unk
     }
 
     /// Loads a tokenizer from a file.
@@ -35,45 +1040,757 @@ impl Tokenizer {
             .map_err(serde_json::Error::custom)
     }
 
+    /// Creates a builder configuring a tokenizer with the set of punctuation characters to normalize.
+    pub fn builder(punct: &str) -> TokenizerBuilder {
+        TokenizerBuilder::new(punct)
+    }
+
+    /// Returns the configuration of the tokenizer, which builds an unfitted tokenizer with the same
+    /// options and special tokens.
+    pub fn config(&self) -> TokenizerBuilder {
+        TokenizerBuilder {
+            punct: self.punct.as_str().into(),
+            preprocessors: self.preprocessors.clone(),
+            strip_html: self.strip_html,
+            unicode_normalization: self.unicode_normalization,
+            placeholders: self.placeholders.clone(),
+            collapse_repeats: self.collapse_repeats,
+            emoji_mode: self.emoji_mode,
+            segmentation: self.segmentation.clone().into(),
+            cjk_mode: self.cjk_mode,
+            lowercase: self.lowercase,
+            case_locale: self.case_locale,
+            stopwords: self.stopwords.iter().cloned().collect(),
+            min_token_len: self.min_token_len,
+            spell_corrector: self.spell_corrector.clone(),
+            stem: self.stem,
+            lemmas: self
+                .lemmas
+                .iter()
+                .map(|(form, lemma)| (form.clone(), lemma.clone()))
+                .collect(),
+            negation_window: self.negation_window,
+            negation_words: self.negation_words.iter().cloned().collect(),
+            ngram_range: self.ngram_range,
+            token_mode: self.token_mode,
+            char_ngram_range: self.char_ngram_range,
+            skipgram_window: self.skipgram_window,
+            max_vocab: self.max_vocab,
+            phrase_detection: self.phrase_detection,
+            special_tokens: self.special_tokens().map(|token| token.into()).collect(),
+        }
+    }
+
+    /// Creates a tokenizer whose vocabulary is read from a Hugging Face `vocab.txt` file, with one
+    /// token per line at the index of the line. The `[UNK]` or [`Self::UNK_TOKEN`] token, if any,
+    /// becomes the unknown word token. Token frequencies are unknown and set to zero.
+    pub fn from_hf_vocab(punct: &str, file: &mut dyn Read) -> std::io::Result<Self> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)?;
+
+        let mut tokenizer = Self::new(punct)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        for line in buffer.lines() {
+            if !tokenizer.dict.insert(line.into()) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Duplicate vocabulary token: {:?}", line),
+                ));
+            }
+        }
+        tokenizer.term_frequencies = vec![0; tokenizer.dict.len()];
+        tokenizer.document_frequencies = vec![0; tokenizer.dict.len()];
+        tokenizer.unk = ["[UNK]", Self::UNK_TOKEN]
+            .iter()
+            .find_map(|token| tokenizer.dict.get_index_of(*token));
+        Ok(tokenizer)
+    }
+
+    /// Saves the vocabulary as a Hugging Face `vocab.txt` file, with one token per line in index
+    /// order.
+    pub fn save_hf_vocab(&self, file: &mut dyn std::io::Write) -> std::io::Result<()> {
+        for token in self.dict.iter() {
+            writeln!(file, "{}", token)?;
+        }
+        Ok(())
+    }
+
+    /// Exports the vocabulary as a TSV file with one `token<TAB>index<TAB>count` line per token, in
+    /// index order, where the count is the number of occurrences of the token in the fitted texts.
+    pub fn export_vocab(&self, file: &mut dyn std::io::Write) -> std::io::Result<()> {
+        for (idx, token) in self.dict.iter().enumerate() {
+            writeln!(file, "{}\t{}\t{}", token, idx, self.term_frequencies[idx])?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the vocabulary with the one of a TSV file in the format of [`Self::export_vocab`],
+    /// e.g. after hand-editing it to remove PII tokens. Tokens are reindexed consecutively in index
+    /// order, and the returned remapping from the file indices should also be applied to the
+    /// classifiers trained with the exported vocabulary. Document frequencies are kept for the tokens
+    /// already in the vocabulary. Fails if the file is malformed or the vocabulary is frozen.
+    pub fn import_vocab(&mut self, file: &mut dyn Read) -> Result<FeatureRemap, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)?;
+
+        let invalid = |line: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Malformed vocabulary line: {:?}", line),
+            )
+        };
+        let mut entries = buffer
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut fields = line.split('\t');
+                match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                    (Some(token), Some(idx), Some(count), None) => Ok((
+                        idx.parse::<usize>().map_err(|_| invalid(line))?,
+                        token,
+                        count.parse::<usize>().map_err(|_| invalid(line))?,
+                    )),
+                    _ => Err(invalid(line)),
+                }
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_unstable_by_key(|&(idx, _, _)| idx);
+
+        for pair in entries.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Duplicate vocabulary index: {}", pair[0].0),
+                )
+                .into());
+            }
+        }
+        let mut dict = IndexSet::with_capacity(entries.len());
+        for &(_, token, _) in entries.iter() {
+            if !dict.insert(Box::<str>::from(token)) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Duplicate vocabulary token: {:?}", token),
+                )
+                .into());
+            }
+        }
+
+        let old_n_features = entries.last().map_or(0, |&(idx, _, _)| idx + 1);
+        let mut present = vec![false; old_n_features];
+        entries.iter().for_each(|&(idx, _, _)| present[idx] = true);
+
+        self.document_frequencies = dict
+            .iter()
+            .map(|token| {
+                self.dict
+                    .get_index_of(token)
+                    .map_or(0, |idx| self.document_frequencies[idx])
+            })
+            .collect();
+        self.term_frequencies = entries.iter().map(|&(_, _, count)| count).collect();
+        self.n_special = self
+            .special_tokens()
+            .zip(dict.iter())
+            .take_while(|(special, token)| *special == &***token)
+            .count();
+        self.unk = self.unk.and_then(|unk| dict.get_index_of(&self.dict[unk]));
+        self.dict = dict;
+
+        Ok(FeatureRemap::new(old_n_features, |idx| present[idx]))
+    }
+
+    /// Forbids fitting new texts, so that the vocabulary stays in sync with the classifiers trained
+    /// with the tokenizer.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Whether fitting new texts is forbidden.
+    #[inline]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
     /// Fits the tokenizer on the provided text and returns the tokens of the text.
-    pub fn fit(&mut self, text: &str) -> Vec<usize> {
-        let normalized = self.normalize(text);
-        normalized
-            .split_whitespace()
-            .map(|w| self.dict.insert_full(w.into()))
+    /// Fails if the vocabulary is frozen.
+    pub fn fit(&mut self, text: &str) -> Result<Vec<usize>, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+
+        let (words, terms) = self.analyze(text);
+        Ok(self.fit_analyzed(&words, terms))
+    }
+
+    /// Fits the tokenizer on a stream of texts without materializing them, and returns the number of
+    /// fitted texts. Fails if the vocabulary is frozen.
+    pub fn fit_iter<T: AsRef<str>>(
+        &mut self,
+        texts: impl IntoIterator<Item = T>,
+    ) -> Result<usize, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+
+        let mut n_texts = 0;
+        for text in texts {
+            let (words, terms) = self.analyze(text.as_ref());
+            self.fit_analyzed(&words, terms);
+            n_texts += 1;
+        }
+        Ok(n_texts)
+    }
+
+    /// Fits the tokenizer on a batch of texts and returns the tokens of every text. The texts are
+    /// split into terms in parallel, then added to the vocabulary in order, so that the result is
+    /// the same as fitting them one by one. Fails if the vocabulary is frozen.
+    pub fn fit_batch<S: AsRef<str> + Sync>(
+        &mut self,
+        texts: &[S],
+    ) -> Result<Vec<Vec<usize>>, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+
+        let analyzed: Vec<_> = texts
+            .par_iter()
+            .map(|text| self.analyze(text.as_ref()))
+            .collect();
+        Ok(analyzed
+            .into_iter()
+            .map(|(words, terms)| self.fit_analyzed(&words, terms))
+            .collect())
+    }
+
+    /// Splits the text into its words and terms.
+    fn analyze(&self, text: &str) -> (Vec<Box<str>>, Vec<Box<str>>) {
+        let text = self.preprocess(text);
+        let words: Vec<Box<str>> = self.words(&text).into_iter().map(|w| w.into()).collect();
+        let terms: Vec<Box<str>> = self
+            .terms(words.iter().map(|w| Term::Borrowed(w)).collect())
+            .into_iter()
+            .map(|w| w.into())
+            .collect();
+        (words, terms)
+    }
+
+    /// Adds the words and terms of a text to the statistics and the vocabulary, and returns the
+    /// tokens of the text.
+    fn fit_analyzed(&mut self, words: &[Box<str>], terms: Vec<Box<str>>) -> Vec<usize> {
+        if self
+            .max_vocab
+            .is_some_and(|max_vocab| self.dict.len() >= 2 * max_vocab)
+        {
+            self.cap_vocab();
+        }
+
+        if self.phrase_detection {
+            self.word_stats.record(words);
+        }
+        let tokens: Vec<usize> = terms
+            .into_iter()
+            .map(|w| self.dict.insert_full(w))
             .map(|(idx, _)| idx)
-            .collect()
+            .collect();
+
+        let mut present = tokens.clone();
+        present.sort_unstable();
+        present.dedup();
+        self.term_frequencies.resize(self.dict.len(), 0);
+        tokens
+            .iter()
+            .for_each(|&token| self.term_frequencies[token] += 1);
+        self.document_frequencies.resize(self.dict.len(), 0);
+        present
+            .into_iter()
+            .for_each(|token| self.document_frequencies[token] += 1);
+        self.n_documents += 1;
+
+        tokens
+    }
+
+    /// Fits the tokenizer on the provided text with every term prefixed, and returns the tokens of
+    /// the text. Fails if the vocabulary is frozen.
+    pub(crate) fn fit_prefixed(
+        &mut self,
+        text: &str,
+        prefix: &str,
+    ) -> Result<Vec<usize>, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+
+        let (words, terms) = self.analyze(text);
+        let terms = terms
+            .into_iter()
+            .map(|term| format!("{}{}", prefix, term).into())
+            .collect();
+        Ok(self.fit_analyzed(&words, terms))
+    }
+
+    /// Returns the token of the term, or the unknown word token if it isn't in the vocabulary.
+    pub(crate) fn lookup(&self, term: &str) -> Option<usize> {
+        self.dict.get_index_of(term).or(self.unk)
+    }
+
+    /// Calls `f` on every term of the text, in order, whether or not it is in the vocabulary.
+    pub(crate) fn for_each_term(&self, text: &str, f: impl FnMut(&str)) {
+        let text = self.preprocess(text);
+        self.terms(self.words(&text))
+            .iter()
+            .map(|term| &**term)
+            .for_each(f);
     }
 
     /// Tokenize the supplied text into a list of tokens.
     pub fn tokenize(&self, text: &str) -> Vec<usize> {
-        let normalized = self.normalize(text);
-        normalized
-            .split_whitespace()
-            .filter_map(|w| self.dict.get_index_of(w))
-            .collect()
+        let mut tokens = Vec::new();
+        self.tokenize_into(text, &mut tokens);
+        tokens
     }
 
     /// Tokenize the supplied text into a vector representing the presence of words.
     pub fn tokenize_sparse(&self, text: &str) -> Vec<usize> {
-        let normalized = self.normalize(text);
-        let mut tokens: Vec<usize> = normalized
-            .split_whitespace()
-            .filter_map(|w| self.dict.get_index_of(w))
-            .collect();
+        let mut tokens = Vec::new();
+        self.tokenize_sparse_into(text, &mut tokens);
+        tokens
+    }
+
+    /// Tokenize the supplied text into a list of tokens written to `out`, which is cleared first so
+    /// that its allocation can be reused across texts.
+    pub fn tokenize_into(&self, text: &str, out: &mut Vec<usize>) {
+        out.clear();
+        let text = self.preprocess(text);
+        out.extend(
+            self.terms(self.words(&text))
+                .into_iter()
+                .filter_map(|w| self.dict.get_index_of(&*w).or(self.unk)),
+        );
+    }
+
+    /// Tokenize the supplied text into a vector representing the presence of words written to
+    /// `out`, which is cleared first so that its allocation can be reused across texts.
+    pub fn tokenize_sparse_into(&self, text: &str, out: &mut Vec<usize>) {
+        self.tokenize_into(text, out);
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    /// Tokenize the supplied text into a bitmap of the present tokens, sized to the vocabulary.
+    pub fn tokenize_bitset(&self, text: &str) -> FixedBitSet {
+        let mut present = FixedBitSet::with_capacity(self.dict.len());
+        let text = self.preprocess(text);
+        self.terms(self.words(&text))
+            .into_iter()
+            .filter_map(|w| self.dict.get_index_of(&*w).or(self.unk))
+            .for_each(|token| present.insert(token));
+        present
+    }
 
+    /// Tokenize the supplied text into a sparse vector of (token, count) pairs, sorted by token.
+    pub fn tokenize_counts(&self, text: &str) -> Vec<(usize, u32)> {
+        let mut tokens = self.tokenize(text);
         tokens.sort_unstable();
-        tokens.dedup();
 
-        tokens
+        let mut counts: Vec<(usize, u32)> = Vec::new();
+        for token in tokens {
+            match counts.last_mut() {
+                Some((last, count)) if *last == token => *count += 1,
+                _ => counts.push((token, 1)),
+            }
+        }
+        counts
+    }
+
+    /// Builds the terms making up the vocabulary from the words of a text, that is the n-grams of its
+    /// words joined by spaces and/or the character n-grams of its words.
+    fn terms<'a>(&self, words: Vec<Term<'a>>) -> Vec<Term<'a>> {
+        match self.token_mode {
+            TokenMode::Words => self.word_terms(words),
+            TokenMode::CharNgrams => self.char_ngrams(&words),
+            TokenMode::WordsAndCharNgrams => {
+                let mut terms = self.char_ngrams(&words);
+                terms.extend(self.word_terms(words));
+                terms
+            }
+        }
+    }
+
+    /// Returns the word n-grams followed by the skip-grams of the words.
+    fn word_terms<'a>(&self, words: Vec<Term<'a>>) -> Vec<Term<'a>> {
+        let skipgrams = self.skipgrams(&words);
+        let mut terms = self.word_ngrams(words);
+        terms.extend(skipgrams);
+        terms
+    }
+
+    /// Returns the pairs of non-adjacent words within the skip-gram window.
+    fn skipgrams<'a>(&self, words: &[Term<'_>]) -> Vec<Term<'a>> {
+        let mut skipgrams = Vec::new();
+
+        for (i, first) in words.iter().enumerate() {
+            for second in words
+                .iter()
+                .skip(i + 2)
+                .take(self.skipgram_window.saturating_sub(1))
+            {
+                skipgrams.push(Term::Owned(format!("{} … {}", first, second)));
+            }
+        }
+        skipgrams
     }
 
-    /// Normalize punctuation in the passed in text.
+    /// Returns the n-grams of the words joined by spaces.
+    fn word_ngrams<'a>(&self, words: Vec<Term<'a>>) -> Vec<Term<'a>> {
+        let (min_n, max_n) = self.ngram_range;
+
+        if (min_n, max_n) == (1, 1) {
+            return words;
+        }
+
+        let mut ngrams = Vec::new();
+        for n in min_n..=max_n.min(words.len()) {
+            if n == 1 {
+                ngrams.extend(words.iter().cloned());
+            } else {
+                ngrams.extend(words.windows(n).map(|gram| Term::Owned(gram.join(" "))));
+            }
+        }
+        ngrams
+    }
+
+    /// Returns the character n-grams of each word padded with a space on each side.
+    fn char_ngrams<'a>(&self, words: &[Term<'_>]) -> Vec<Term<'a>> {
+        let (min_n, max_n) = self.char_ngram_range;
+        let mut ngrams = Vec::new();
+
+        for word in words {
+            let padded: Vec<char> = std::iter::once(' ')
+                .chain(word.chars())
+                .chain(std::iter::once(' '))
+                .collect();
+
+            for n in min_n..=max_n {
+                ngrams.extend(
+                    padded
+                        .windows(n)
+                        .map(|gram| Term::Owned(gram.iter().collect())),
+                );
+            }
+        }
+        ngrams
+    }
+
+    /// Splits the preprocessed text into words, dropping the stop words then spell correcting,
+    /// lemmatizing and stemming the others, and finally merging the detected phrases.
+    fn words<'a>(&'a self, text: &'a str) -> Vec<Term<'a>> {
+        let stemmer = self.stem.then(|| Stemmer::create(Algorithm::English));
+        // Spell correction and stemming dominate the tokenization cost, while a few words make up
+        // most of the text, so their results are cached and shared without copying. The cache is
+        // sharded and each shard only locked briefly, so that texts can be tokenized in parallel.
+        let cached = self.stem || self.spell_corrector.is_some();
+
+        let words = self
+            .negated_words(text)
+            .filter(|(word, _)| !self.stopwords.contains(*word))
+            .filter(|(word, _)| word.chars().count() >= self.min_token_len)
+            .map(|(word, negated)| {
+                let word = if cached {
+                    self.cached_normalize_word(word, stemmer.as_ref())
+                } else {
+                    self.normalize_word(word, stemmer.as_ref()).into()
+                };
+                if negated {
+                    Term::Owned(format!("not_{}", word))
+                } else {
+                    word
+                }
+            })
+            .collect();
+
+        self.merge_phrases(words)
+    }
+
+    /// Spell corrects, lemmatizes and stems the word.
+    fn normalize_word<'a>(&'a self, word: &'a str, stemmer: Option<&Stemmer>) -> Cow<'a, str> {
+        let word = match &self.spell_corrector {
+            Some(corrector) => corrector.correct(word).unwrap_or(word),
+            None => word,
+        };
+        let word = self.lemmas.get(word).map_or(word, |lemma| &**lemma);
+        match stemmer {
+            Some(stemmer) => stemmer.stem(word),
+            None => Cow::Borrowed(word),
+        }
+    }
+
+    /// Normalizes the word as [`Self::normalize_word`], looking its normalized form up in the cache
+    /// first.
+    fn cached_normalize_word<'a>(&'a self, word: &'a str, stemmer: Option<&Stemmer>) -> Term<'a> {
+        let hash = word.bytes().fold(0u64, |hash, byte| {
+            (hash.rotate_left(5) ^ byte as u64).wrapping_mul(0x517c_c1b7_2722_0a95)
+        });
+        let shard = &self.word_cache[(hash >> 32) as usize % self.word_cache.len()];
+        let lock = || shard.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(normalized) = lock().get(word) {
+            return match normalized {
+                Some(normalized) => Term::Shared(normalized.clone()),
+                None => Term::Borrowed(word),
+            };
+        }
+
+        match self.normalize_word(word, stemmer) {
+            Cow::Borrowed(normalized) if normalized == word => {
+                lock().insert(word, None);
+                Term::Borrowed(word)
+            }
+            normalized => {
+                let normalized: Arc<str> = normalized.into();
+                lock().insert(word, Some(normalized.clone()));
+                Term::Shared(normalized)
+            }
+        }
+    }
+
+    /// Clears the cached normalized words, whose normalization changed.
+    fn clear_word_cache(&mut self) {
+        self.word_cache.iter_mut().for_each(|shard| {
+            shard
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear()
+        });
+    }
+
+    /// Splits the preprocessed text into words, along with whether each word is in the scope of a
+    /// preceding negation word.
+    fn negated_words<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (&'a str, bool)> {
+        let mut remaining = 0;
+        let words = self.cjk_mode.split(
+            self.segmentation
+                .segment(text, &self.punct, |token| self.is_whole_token(token)),
+        );
+        words.into_iter().map(move |word| {
+            if self.negation_window == 0 {
+                (word, false)
+            } else if self.negation_words.contains(word) {
+                remaining = self.negation_window;
+                (word, false)
+            } else if word.chars().all(|c| ".,!?;:".contains(c)) {
+                remaining = 0;
+                (word, false)
+            } else {
+                let negated = remaining > 0;
+                remaining = remaining.saturating_sub(1);
+                (word, negated)
+            }
+        })
+    }
+
+    /// Returns whether the token is inserted by an enabled processing stage or reserved in the
+    /// vocabulary, and thus kept whole by segmentation: the placeholder tokens of the enabled
+    /// placeholders, emoji description tokens when emoji are described, and the unknown word and
+    /// padding tokens once registered.
+    fn is_whole_token(&self, token: &str) -> bool {
+        self.placeholders
+            .iter()
+            .any(|placeholder| placeholder.token() == token)
+            || (self.emoji_mode == EmojiMode::Describe && EmojiMode::is_description(token))
+            || ([Self::UNK_TOKEN, Self::PAD_TOKEN].contains(&token)
+                && self
+                    .dict
+                    .get_index_of(token)
+                    .is_some_and(|idx| self.is_reserved(idx)))
+    }
+
+    /// Merges the pairs of adjacent words which are detected phrases, from left to right.
+    fn merge_phrases<'a>(&self, words: Vec<Term<'a>>) -> Vec<Term<'a>> {
+        if self.phrases.is_empty() {
+            return words;
+        }
+
+        let mut merged = Vec::with_capacity(words.len());
+        let mut words = words.into_iter().peekable();
+
+        while let Some(word) = words.next() {
+            let is_phrase = words.peek().is_some_and(|next| {
+                self.phrases
+                    .get(&*word)
+                    .is_some_and(|seconds| seconds.contains(&**next))
+            });
+
+            if is_phrase {
+                let next = words.next().unwrap();
+                merged.push(Term::Owned(format!("{}_{}", word, next)));
+            } else {
+                merged.push(word);
+            }
+        }
+        merged
+    }
+
+    /// Normalize punctuation in the passed in text, returning its words separated by single spaces.
+    /// Tokenization doesn't build this string, splitting the words out of the text on the fly.
     pub fn normalize(&self, text: &str) -> Box<str> {
-        let re = Regex::new(&self.punct).unwrap();
-        let result: Cow<'_, str> = re.replace_all(text, " $1 ");
-        let collapsed = Regex::new(r"\s{2,}").unwrap().replace_all(&result, " ");
-        collapsed.trim().into()
+        let text = self.preprocess(text);
+        let words = match self.segmentation {
+            Segmenter::Whitespace => self
+                .segmentation
+                .segment(&text, &self.punct, |token| self.is_whole_token(token)),
+            Segmenter::TokenPattern(_) | Segmenter::UnicodeWords => {
+                text.split_whitespace().collect()
+            }
+        };
+        words.join(" ").into()
+    }
+
+    /// Applies the text-level processing stages preceding segmentation, only allocating if a stage
+    /// changes the text.
+    fn preprocess<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut text = Cow::Borrowed(text);
+        let custom = self
+            .custom_preprocessors
+            .iter()
+            .map(|p| &**p as &dyn TextPreprocessor);
+        for preprocessor in self
+            .preprocessors
+            .iter()
+            .map(|p| p as &dyn TextPreprocessor)
+            .chain(custom)
+        {
+            if let Cow::Owned(processed) = preprocessor.process(&text) {
+                text = Cow::Owned(processed);
+            }
+        }
+        if self.strip_html {
+            if let Cow::Owned(stripped) = strip_html(&text) {
+                text = Cow::Owned(stripped);
+            }
+        }
+        if let Cow::Owned(normalized) = self.unicode_normalization.apply(&text) {
+            text = Cow::Owned(normalized);
+        }
+        for placeholder in self.placeholders.iter() {
+            if let Cow::Owned(replaced) = placeholder.replace(&text) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        if self.collapse_repeats {
+            if let Cow::Owned(collapsed) = collapse_repeats(&text) {
+                text = Cow::Owned(collapsed);
+            }
+        }
+        if let Cow::Owned(split) = self.emoji_mode.apply(&text) {
+            text = Cow::Owned(split);
+        }
+        if self.lowercase {
+            if let Cow::Owned(folded) = self.case_locale.fold_case(&text) {
+                text = Cow::Owned(folded);
+            }
+        }
+        text
+    }
+
+    /// Drops the tokens which aren't kept by the remapping and reindexes the remaining ones, so that
+    /// the vocabulary matches a pruned classifier. Fails if the vocabulary is frozen.
+    pub fn remap(&mut self, remap: &FeatureRemap) -> Result<(), RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+        self.remap_vocab(remap);
+        Ok(())
+    }
+
+    /// Applies the remapping to the vocabulary, as [`Self::remap`].
+    fn remap_vocab(&mut self, remap: &FeatureRemap) {
+        assert_eq!(
+            remap.old_n_features(),
+            self.dict.len(),
+            "Remapping must cover the whole vocabulary"
+        );
+        self.dict = remap
+            .kept()
+            .iter()
+            .filter_map(|&idx| self.dict.get_index(idx).cloned())
+            .collect();
+        self.term_frequencies = remap
+            .kept()
+            .iter()
+            .map(|&idx| self.term_frequencies[idx])
+            .collect();
+        self.document_frequencies = remap
+            .kept()
+            .iter()
+            .map(|&idx| self.document_frequencies[idx])
+            .collect();
+        self.unk = self.unk.and_then(|unk| remap.get(unk));
+    }
+
+    /// Drops the tokens contained in fewer than `min_df` fitted texts or in more than the `max_df`
+    /// fraction of them, and reindexes the remaining ones. Returns the remapping, which should also
+    /// be applied to classifiers trained with the tokenizer, or fails if the vocabulary is frozen.
+    pub fn prune_document_frequency(
+        &mut self,
+        min_df: usize,
+        max_df: f64,
+    ) -> Result<FeatureRemap, RnbError> {
+        if self.frozen {
+            return Err(RnbError::FrozenVocabulary);
+        }
+        assert!(
+            (0.0..=1.0).contains(&max_df),
+            "Maximal document frequency must be in the [0, 1] range"
+        );
+
+        let max_count = max_df * self.n_documents as f64;
+        let remap = FeatureRemap::new(self.dict.len(), |idx| {
+            let frequency = self.document_frequencies[idx];
+            self.is_reserved(idx) || (frequency >= min_df && frequency as f64 <= max_count)
+        });
+        self.remap_vocab(&remap);
+        Ok(remap)
+    }
+
+    /// Returns the number of occurrences of each token in the fitted texts.
+    pub fn term_frequencies(&self) -> &[usize] {
+        &self.term_frequencies
+    }
+
+    /// Returns the number of fitted texts containing each token.
+    pub fn document_frequencies(&self) -> &[usize] {
+        &self.document_frequencies
+    }
+
+    /// Returns the number of occurrences of the token in the fitted texts, if it is known.
+    pub fn term_frequency(&self, idx: usize) -> Option<usize> {
+        self.term_frequencies.get(idx).copied()
+    }
+
+    /// Returns the number of fitted texts containing the token, if it is known.
+    pub fn document_frequency(&self, idx: usize) -> Option<usize> {
+        self.document_frequencies.get(idx).copied()
+    }
+
+    /// The number of fitted texts.
+    #[inline]
+    pub fn n_documents(&self) -> usize {
+        self.n_documents
+    }
+
+    /// Returns the text of the token at the specified index, if any.
+    pub fn token(&self, idx: usize) -> Option<&str> {
+        self.dict.get_index(idx).map(|token| &**token)
+    }
+
+    /// Returns an iterator over the (index, text) pairs of the vocabulary, in index order.
+    pub fn tokens(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.dict.iter().map(|token| &**token).enumerate()
     }
 
     /// The total count of tokens
@@ -82,3 +1799,364 @@ impl Tokenizer {
         self.dict.len()
     }
 }
+
+/// Fluent configuration of a [`Tokenizer`], holding every option but the fitted vocabulary, which
+/// can be serialized to share the configuration between trainings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenizerBuilder {
+    /// The set of punctuation characters to normalize.
+    punct: Box<str>,
+    /// See [`Tokenizer::set_preprocessors`].
+    preprocessors: Vec<Preprocessor>,
+    /// See [`Tokenizer::set_strip_html`].
+    strip_html: bool,
+    /// See [`Tokenizer::set_unicode_normalization`].
+    unicode_normalization: UnicodeNormalization,
+    /// See [`Tokenizer::set_placeholders`].
+    placeholders: Vec<Placeholder>,
+    /// See [`Tokenizer::set_collapse_repeats`].
+    collapse_repeats: bool,
+    /// See [`Tokenizer::set_emoji_mode`].
+    emoji_mode: EmojiMode,
+    /// See [`Tokenizer::set_segmentation`].
+    segmentation: Segmentation,
+    /// See [`Tokenizer::set_cjk_mode`].
+    cjk_mode: CjkMode,
+    /// See [`Tokenizer::set_lowercase`].
+    lowercase: bool,
+    /// See [`Tokenizer::set_case_locale`].
+    case_locale: CaseLocale,
+    /// See [`Tokenizer::set_stopwords`].
+    stopwords: Vec<Box<str>>,
+    /// See [`Tokenizer::set_min_token_len`].
+    min_token_len: usize,
+    /// See [`Tokenizer::set_spell_corrector`].
+    spell_corrector: Option<SpellCorrector>,
+    /// See [`Tokenizer::set_stem`].
+    stem: bool,
+    /// See [`Tokenizer::set_lemmas`].
+    lemmas: Vec<(Box<str>, Box<str>)>,
+    /// See [`Tokenizer::set_negation`].
+    negation_window: usize,
+    /// See [`Tokenizer::set_negation`].
+    negation_words: Vec<Box<str>>,
+    /// See [`Tokenizer::set_ngram_range`].
+    ngram_range: (usize, usize),
+    /// See [`Tokenizer::set_token_mode`].
+    token_mode: TokenMode,
+    /// See [`Tokenizer::set_char_ngram_range`].
+    char_ngram_range: (usize, usize),
+    /// See [`Tokenizer::set_skipgram_window`].
+    skipgram_window: usize,
+    /// See [`Tokenizer::set_max_vocab`].
+    max_vocab: Option<usize>,
+    /// See [`Tokenizer::set_phrase_detection`].
+    phrase_detection: bool,
+    /// See [`Tokenizer::register_special_token`].
+    special_tokens: Vec<Box<str>>,
+}
+
+impl TokenizerBuilder {
+    /// Creates a builder with the default options and the set of punctuation characters to
+    /// normalize.
+    pub fn new(punct: &str) -> Self {
+        Self {
+            punct: punct.into(),
+            preprocessors: Vec::new(),
+            strip_html: false,
+            unicode_normalization: UnicodeNormalization::default(),
+            placeholders: Vec::new(),
+            collapse_repeats: false,
+            emoji_mode: EmojiMode::default(),
+            segmentation: Segmentation::default(),
+            cjk_mode: CjkMode::default(),
+            lowercase: false,
+            case_locale: CaseLocale::default(),
+            stopwords: Vec::new(),
+            min_token_len: 0,
+            spell_corrector: None,
+            stem: false,
+            lemmas: Vec::new(),
+            negation_window: 0,
+            negation_words: Vec::new(),
+            ngram_range: (1, 1),
+            token_mode: TokenMode::default(),
+            char_ngram_range: (3, 5),
+            skipgram_window: 0,
+            max_vocab: None,
+            phrase_detection: false,
+            special_tokens: Vec::new(),
+        }
+    }
+
+    /// Loads a configuration from a file.
+    pub fn load_from_file(file: &mut dyn Read) -> Result<Self, serde_json::Error> {
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(serde_json::Error::custom)?;
+        serde_json::from_str(&buffer)
+    }
+
+    /// Saves the configuration to a file.
+    pub fn save_to_file(&self, file: &mut dyn std::io::Write) -> Result<(), serde_json::Error> {
+        let serialized = serde_json::to_string(self)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(serde_json::Error::custom)
+    }
+
+    /// See [`Tokenizer::set_preprocessors`].
+    pub fn preprocessors(mut self, preprocessors: impl IntoIterator<Item = Preprocessor>) -> Self {
+        self.preprocessors = preprocessors.into_iter().collect();
+        self
+    }
+
+    /// See [`Tokenizer::set_strip_html`].
+    pub fn strip_html(mut self, strip_html: bool) -> Self {
+        self.strip_html = strip_html;
+        self
+    }
+
+    /// See [`Tokenizer::set_unicode_normalization`].
+    pub fn unicode_normalization(mut self, unicode_normalization: UnicodeNormalization) -> Self {
+        self.unicode_normalization = unicode_normalization;
+        self
+    }
+
+    /// See [`Tokenizer::set_placeholders`].
+    pub fn placeholders(mut self, placeholders: impl IntoIterator<Item = Placeholder>) -> Self {
+        self.placeholders = placeholders.into_iter().collect();
+        self
+    }
+
+    /// See [`Tokenizer::set_collapse_repeats`].
+    pub fn collapse_repeats(mut self, collapse_repeats: bool) -> Self {
+        self.collapse_repeats = collapse_repeats;
+        self
+    }
+
+    /// See [`Tokenizer::set_emoji_mode`].
+    pub fn emoji_mode(mut self, emoji_mode: EmojiMode) -> Self {
+        self.emoji_mode = emoji_mode;
+        self
+    }
+
+    /// See [`Tokenizer::set_segmentation`].
+    pub fn segmentation(mut self, segmentation: Segmentation) -> Self {
+        self.segmentation = segmentation;
+        self
+    }
+
+    /// See [`Tokenizer::set_cjk_mode`].
+    pub fn cjk_mode(mut self, cjk_mode: CjkMode) -> Self {
+        self.cjk_mode = cjk_mode;
+        self
+    }
+
+    /// See [`Tokenizer::set_lowercase`].
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// See [`Tokenizer::set_case_locale`].
+    pub fn case_locale(mut self, case_locale: CaseLocale) -> Self {
+        self.case_locale = case_locale;
+        self
+    }
+
+    /// See [`Tokenizer::set_stopwords`].
+    pub fn stopwords<'a>(mut self, stopwords: impl IntoIterator<Item = &'a str>) -> Self {
+        self.stopwords = stopwords.into_iter().map(|word| word.into()).collect();
+        self
+    }
+
+    /// See [`Tokenizer::set_min_token_len`].
+    pub fn min_token_len(mut self, min_token_len: usize) -> Self {
+        self.min_token_len = min_token_len;
+        self
+    }
+
+    /// See [`Tokenizer::set_spell_corrector`].
+    pub fn spell_corrector(mut self, spell_corrector: Option<SpellCorrector>) -> Self {
+        self.spell_corrector = spell_corrector;
+        self
+    }
+
+    /// See [`Tokenizer::set_stem`].
+    pub fn stem(mut self, stem: bool) -> Self {
+        self.stem = stem;
+        self
+    }
+
+    /// See [`Tokenizer::set_lemmas`].
+    pub fn lemmas<'a>(mut self, lemmas: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        self.lemmas = lemmas
+            .into_iter()
+            .map(|(form, lemma)| (form.into(), lemma.into()))
+            .collect();
+        self
+    }
+
+    /// See [`Tokenizer::set_negation`].
+    pub fn negation<'a>(mut self, window: usize, words: impl IntoIterator<Item = &'a str>) -> Self {
+        self.negation_window = window;
+        self.negation_words = words.into_iter().map(|word| word.into()).collect();
+        self
+    }
+
+    /// See [`Tokenizer::set_ngram_range`].
+    pub fn ngram_range(mut self, min_n: usize, max_n: usize) -> Self {
+        self.ngram_range = (min_n, max_n);
+        self
+    }
+
+    /// See [`Tokenizer::set_token_mode`].
+    pub fn token_mode(mut self, token_mode: TokenMode) -> Self {
+        self.token_mode = token_mode;
+        self
+    }
+
+    /// See [`Tokenizer::set_char_ngram_range`].
+    pub fn char_ngram_range(mut self, min_n: usize, max_n: usize) -> Self {
+        self.char_ngram_range = (min_n, max_n);
+        self
+    }
+
+    /// See [`Tokenizer::set_skipgram_window`].
+    pub fn skipgram_window(mut self, window: usize) -> Self {
+        self.skipgram_window = window;
+        self
+    }
+
+    /// See [`Tokenizer::set_max_vocab`].
+    pub fn max_vocab(mut self, max_vocab: Option<usize>) -> Self {
+        self.max_vocab = max_vocab;
+        self
+    }
+
+    /// See [`Tokenizer::set_phrase_detection`].
+    pub fn phrase_detection(mut self, phrase_detection: bool) -> Self {
+        self.phrase_detection = phrase_detection;
+        self
+    }
+
+    /// Adds a special token registered at the next fixed index of the vocabulary. See
+    /// [`Tokenizer::register_special_token`].
+    pub fn special_token(mut self, token: &str) -> Self {
+        self.special_tokens.push(token.into());
+        self
+    }
+
+    /// Creates an unfitted tokenizer with the configured options. Returns an error if a regex is
+    /// invalid, and panics if another option is invalid.
+    pub fn build(&self) -> Result<Tokenizer, RnbError> {
+        let mut tokenizer = Tokenizer::new(&self.punct)?;
+        tokenizer.set_preprocessors(self.preprocessors.iter().cloned());
+        tokenizer.set_strip_html(self.strip_html);
+        tokenizer.set_unicode_normalization(self.unicode_normalization);
+        tokenizer.set_placeholders(self.placeholders.iter().copied());
+        tokenizer.set_collapse_repeats(self.collapse_repeats);
+        tokenizer.set_emoji_mode(self.emoji_mode);
+        tokenizer.set_segmentation(self.segmentation.clone())?;
+        tokenizer.set_cjk_mode(self.cjk_mode);
+        tokenizer.set_lowercase(self.lowercase);
+        tokenizer.set_case_locale(self.case_locale);
+        tokenizer.set_stopwords(self.stopwords.iter().map(|word| &**word));
+        tokenizer.set_min_token_len(self.min_token_len);
+        tokenizer.set_spell_corrector(self.spell_corrector.clone());
+        tokenizer.set_stem(self.stem);
+        tokenizer.set_lemmas(self.lemmas.iter().map(|(form, lemma)| (&**form, &**lemma)));
+        tokenizer.set_negation(
+            self.negation_window,
+            self.negation_words.iter().map(|word| &**word),
+        );
+        tokenizer.set_ngram_range(self.ngram_range.0, self.ngram_range.1);
+        tokenizer.set_token_mode(self.token_mode);
+        tokenizer.set_char_ngram_range(self.char_ngram_range.0, self.char_ngram_range.1);
+        tokenizer.set_skipgram_window(self.skipgram_window);
+        tokenizer.set_max_vocab(self.max_vocab);
+        tokenizer.set_phrase_detection(self.phrase_detection);
+        for token in self.special_tokens.iter() {
+            tokenizer.register_special_token(token)?;
+        }
+        Ok(tokenizer)
+    }
+}
+
+/// Serializes regexes as their pattern, compiling them back on deserialization.
+pub(crate) mod regex_serde {
+    use regex::Regex;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(regex.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map_err(D::Error::custom)
+    }
+}
+
+/// Collapses the runs of a repeated letter to two characters and of a repeated punctuation or
+/// symbol character to one, only allocating if a run is collapsed.
+pub(crate) fn collapse_repeats(text: &str) -> Cow<'_, str> {
+    let mut collapsed: Option<String> = None;
+    let mut previous = None;
+    let mut run = 0;
+    for (idx, c) in text.char_indices() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        previous = Some(c);
+        let max_run = if c.is_alphabetic() {
+            2
+        } else if c.is_numeric() || c.is_whitespace() {
+            usize::MAX
+        } else {
+            1
+        };
+        if run > max_run {
+            collapsed.get_or_insert_with(|| {
+                let mut collapsed = String::with_capacity(text.len());
+                collapsed.push_str(&text[..idx]);
+                collapsed
+            });
+        } else if let Some(collapsed) = collapsed.as_mut() {
+            collapsed.push(c);
+        }
+    }
+    collapsed.map_or(Cow::Borrowed(text), Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frozen_vocabulary_is_left_untouched() {
+        let mut tokenizer = Tokenizer::new("[.,!?]").unwrap();
+        tokenizer.set_max_vocab(Some(1));
+        tokenizer.fit("the quick brown fox").unwrap();
+        tokenizer.freeze();
+        let token_count = tokenizer.token_count();
+
+        let frozen = |result: Result<(), RnbError>| {
+            assert!(matches!(result, Err(RnbError::FrozenVocabulary)));
+        };
+        frozen(tokenizer.limit_vocab().map(|_| ()));
+        frozen(tokenizer.reserve_unk().map(|_| ()));
+        frozen(
+            tokenizer
+                .register_special_token(Tokenizer::PAD_TOKEN)
+                .map(|_| ()),
+        );
+        frozen(
+            tokenizer
+                .import_vocab(&mut "fox\t0\t1\n".as_bytes())
+                .map(|_| ()),
+        );
+        frozen(tokenizer.remap(&FeatureRemap::new(token_count, |idx| idx == 0)));
+        frozen(tokenizer.prune_document_frequency(2, 1.0).map(|_| ()));
+        assert_eq!(tokenizer.token_count(), token_count);
+        assert_eq!(tokenizer.unk_token(), None);
+    }
+}